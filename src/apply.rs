@@ -0,0 +1,299 @@
+//! Apply the edited json document (the `-O json` output) back to the files.
+//!
+//! The `keyword` and `view_content` of crumbs can be changed, and the crumbs
+//! with `"remove": true` are removed. Every crumb in the document has to be
+//! the same crumb in the file, or nothing is applied.
+
+use super::config::{Config, KeywordRegistry};
+use super::datatypes::*;
+use super::fs_operation;
+use super::notebook;
+use super::output::{BreadRecord, CrumbRecord, JSON_SCHEMA_VERSION, JsonDocument};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::{self, Read};
+use std::path::Path;
+
+/// what to do with the crumb in file
+#[derive(Debug, PartialEq)]
+enum Action {
+    Keep,
+    /// rewrite the first line with the new content, the tails are kept
+    Head(String),
+    /// rewrite the first line with the new content and remove the tails
+    Whole(String),
+    Remove,
+}
+
+/// the edit of one line
+#[derive(Debug)]
+enum Edit {
+    /// rewrite the crumb at the position with header and content
+    Rewrite(usize, String, String),
+    /// delete the crumb at the position
+    Delete(usize),
+}
+
+/// the changes of one file
+#[derive(Debug)]
+struct FileChanges {
+    file_path: String,
+    /// the crumb id, the content before and after (None if removed)
+    changes: Vec<(String, String, Option<String>)>,
+    edits: HashMap<usize, Edit>,
+}
+
+/// the action of crumb comparing the record with the crumb in file.
+/// The crumb in file is baked without keywords, so its view_content is
+/// the whole content including tails. The keyword in record may be normalized,
+/// it is kept in file if it is the same keyword
+fn action_of(record: &CrumbRecord, crumb: &Crumb, keywords: &KeywordRegistry) -> Action {
+    if record.remove {
+        return Action::Remove;
+    }
+    match &record.keyword {
+        // without keywords, the view_content is the whole content with tails
+        None if record.view_content == crumb.view_content => Action::Keep,
+        None => Action::Whole(record.view_content.clone()),
+        // with keywords, the view_content is the rest of first line after keyword
+        Some(keyword) => {
+            let new = match crumb.content.split_once(':') {
+                Some((prefix, rest)) => {
+                    let old = prefix.trim().trim_start_matches('!');
+                    let keyword = if old.eq_ignore_ascii_case(keyword)
+                        || keywords.canonical(old) == keywords.canonical(keyword)
+                    {
+                        old
+                    } else {
+                        keyword
+                    };
+                    let prefix = prefix.replacen(old, keyword, 1);
+                    if rest.trim_start() == record.view_content {
+                        format!("{}:{}", prefix, rest)
+                    } else {
+                        format!("{}: {}", prefix, record.view_content)
+                    }
+                }
+                None => format!("{}: {}", keyword, record.view_content),
+            };
+            if new == crumb.content {
+                Action::Keep
+            } else {
+                Action::Head(new)
+            }
+        }
+    }
+}
+
+/// the crumb in file is still the one in record
+fn is_same_crumb(record: &CrumbRecord, crumb: &Crumb) -> bool {
+    let lines = crumb.all_lines_num();
+    crumb.line_num == record.line_num
+        && crumb.position == record.position
+        && crumb.content == record.content
+        && lines[1..] == record.tail_lines[..]
+}
+
+/// the changes of bread record comparing with the file
+fn file_changes(record: &BreadRecord, conf: &Config) -> Result<FileChanges, String> {
+    if notebook::is_notebook(&record.file_path) {
+        return Err(format!("cannot apply to notebook {}", record.file_path));
+    }
+    let bread = fs_operation::bake_all_crumbs(Path::new(&record.file_path), conf.group_mode)
+        .map_err(|e| format!("cannot read {}: {}", record.file_path, e))?;
+    let crumbs = bread.as_ref().map(|b| b.crumbs.as_slice()).unwrap_or(&[]);
+
+    let mut changes = vec![];
+    let mut edits = HashMap::new();
+    for r in &record.crumbs {
+        let id = format!("{}:{}", markdown_link_path(&record.file_path), r.line_num);
+        if r.plain {
+            return Err(format!("{} is the plain comment, adopt it first", id));
+        }
+        let crumb = crumbs
+            .iter()
+            .find(|c| is_same_crumb(r, c))
+            .ok_or(format!("{} isn't the same crumb in file anymore", id))?;
+
+        let lines = crumb.all_lines_num_postion_and_header_content();
+        let (line_num, pos, header, _) = lines[0];
+        let mut from = crumb.view_content.clone();
+        let to = match action_of(r, crumb, &conf.keywords) {
+            Action::Keep => continue,
+            Action::Head(to) => {
+                edits.insert(line_num, Edit::Rewrite(pos, header.to_string(), to.clone()));
+                // only the first line changed
+                from = crumb.content.clone();
+                Some(to)
+            }
+            Action::Whole(to) => {
+                edits.insert(line_num, Edit::Rewrite(pos, header.to_string(), to.clone()));
+                for (line_num, pos, _, _) in &lines[1..] {
+                    edits.insert(*line_num, Edit::Delete(*pos));
+                }
+                Some(to)
+            }
+            Action::Remove => {
+                for (line_num, pos, _, _) in &lines {
+                    edits.insert(*line_num, Edit::Delete(*pos));
+                }
+                None
+            }
+        };
+        changes.push((id, from, to));
+    }
+
+    Ok(FileChanges {
+        file_path: record.file_path.clone(),
+        changes,
+        edits,
+    })
+}
+
+/// apply the json document, only print the changes if `dry_run`.
+/// Return the changed files
+fn apply(
+    doc: &JsonDocument,
+    dry_run: bool,
+    conf: &Config,
+) -> Result<Option<HashSet<String>>, String> {
+    // check all files before changing any of them
+    let all = doc
+        .breads
+        .iter()
+        .map(|b| file_changes(b, conf))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let (update, remove) = if dry_run {
+        ("would update", "would remove")
+    } else {
+        ("updated", "removed")
+    };
+    let (mut updated, mut removed) = (0, 0);
+    let mut files_changed = HashSet::new();
+    for FileChanges {
+        file_path,
+        changes,
+        edits,
+    } in all
+    {
+        if changes.is_empty() {
+            continue;
+        }
+        for (id, from, to) in changes {
+            match to {
+                Some(to) => {
+                    updated += 1;
+                    println!("{} {}: {} -> {}", update, id, from, to)
+                }
+                None => {
+                    removed += 1;
+                    println!("{} {}: {}", remove, id, from)
+                }
+            }
+        }
+        if dry_run {
+            continue;
+        }
+
+        fs_operation::edit_lines_on(&file_path, |line_num, line| match edits.get(&line_num) {
+            Some(Edit::Rewrite(pos, header, content)) => Some(fs_operation::adopt_crumb_in_line(
+                &line, *pos, header, content,
+            )),
+            Some(Edit::Delete(pos)) => fs_operation::delete_crumb_in_line(&line, *pos),
+            None => Some(line),
+        })
+        .map_err(|e| format!("cannot write {}: {}", file_path, e))?;
+        files_changed.insert(file_path);
+    }
+    println!(
+        "Total: {} {} crumbs, {} {} crumbs",
+        update, updated, remove, removed
+    );
+    Ok(Some(files_changed).filter(|f| !f.is_empty()))
+}
+
+/// apply the json document in the file, or in stdin if the file is `-` or
+/// not given. The crumbs are found with the keywords and group mode of config.
+/// Return the changed files
+pub(crate) fn run(
+    file: Option<&str>,
+    dry_run: bool,
+    conf: &Config,
+) -> Result<Option<HashSet<String>>, String> {
+    let input = match file {
+        None | Some("-") => {
+            let mut input = String::new();
+            io::stdin()
+                .read_to_string(&mut input)
+                .map_err(|e| e.to_string())?;
+            input
+        }
+        Some(f) => fs::read_to_string(f).map_err(|e| format!("cannot read {}: {}", f, e))?,
+    };
+    let doc: JsonDocument =
+        serde_json::from_str(&input).map_err(|e| format!("invalid json document: {}", e))?;
+    if doc.schema_version != JSON_SCHEMA_VERSION {
+        return Err(format!(
+            "unsupported schema_version {}, should be {}",
+            doc.schema_version, JSON_SCHEMA_VERSION
+        ));
+    }
+    apply(&doc, dry_run, conf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_action_of() {
+        let crumb = Crumb::new(1, 0, "!HACK:  a b...".to_string(), "//".to_string());
+        let record = |keyword: Option<&str>, view: &str| {
+            let mut r = CrumbRecord::from(&crumb);
+            r.keyword = keyword.map(|k| k.to_string());
+            r.view_content = view.to_string();
+            r
+        };
+        let keywords = ["HACK,kludge=warning".parse().unwrap()]
+            .into_iter()
+            .collect::<KeywordRegistry>();
+
+        // with keywords
+        assert_eq!(
+            action_of(&record(Some("HACK"), "a b..."), &crumb, &keywords),
+            Action::Keep
+        );
+        assert_eq!(
+            action_of(&record(Some("FIXME"), "a b..."), &crumb, &keywords),
+            Action::Head("!FIXME:  a b...".to_string())
+        );
+        assert_eq!(
+            action_of(&record(Some("HACK"), "c"), &crumb, &keywords),
+            Action::Head("!HACK: c".to_string())
+        );
+        // the same keyword in other case or its alias is kept
+        assert_eq!(
+            action_of(&record(Some("hack"), "a b..."), &crumb, &keywords),
+            Action::Keep
+        );
+        assert_eq!(
+            action_of(&record(Some("KLUDGE"), "a b..."), &crumb, &keywords),
+            Action::Keep
+        );
+
+        // without keywords
+        assert_eq!(
+            action_of(&record(None, "!HACK:  a b..."), &crumb, &keywords),
+            Action::Keep
+        );
+        assert_eq!(
+            action_of(&record(None, "TODO: c"), &crumb, &keywords),
+            Action::Whole("TODO: c".to_string())
+        );
+
+        let mut r = record(None, "!HACK:  a b...");
+        r.remove = true;
+        assert_eq!(action_of(&r, &crumb, &keywords), Action::Remove);
+    }
+}
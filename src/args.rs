@@ -1,6 +1,6 @@
 //! The arguments of codeitlater are using
 
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use std::{
     ffi::OsString,
     fs::File,
@@ -9,7 +9,12 @@ use std::{
 
 /// Command Line Args
 #[derive(Default, Parser, Debug)]
-#[command(author = "ccQpein", version, about)]
+#[command(
+    author = "ccQpein",
+    version,
+    about,
+    subcommand_precedence_over_arg = true
+)]
 pub struct Args {
     /// What are the filetypes you want to scan.
     #[arg(short, long)]
@@ -23,6 +28,24 @@ pub struct Args {
     #[arg(short, long)]
     pub(crate) keywords: Option<Vec<String>>,
 
+    /// How the keywords of "-k" and "--exclude-keyword" match: literal (default),
+    /// regex, or glob like "JIRA-*"
+    #[arg(long = "keyword-mode")]
+    pub(crate) keyword_mode: Option<String>,
+
+    /// Match the keywords case-sensitively
+    #[arg(long = "case-sensitive")]
+    pub(crate) case_sensitive: bool,
+
+    /// Hide the crumbs of this keyword, matching like "-k"
+    #[arg(long = "exclude-keyword", value_name = "KEYWORD")]
+    pub(crate) exclude_keywords: Vec<String>,
+
+    /// Only the crumbs without keyword: not having the "-k" keywords if given,
+    /// otherwise not starting with the word and colon like "TODO:"
+    #[arg(long = "no-keyword")]
+    pub(crate) no_keyword: bool,
+
     /// Expand dictionary json file path
     #[arg(short, long)]
     pub(crate) jsonx: Option<String>,
@@ -43,7 +66,7 @@ pub struct Args {
     #[arg(long = "fmt")]
     pub(crate) fmt_command: Option<String>,
 
-    /// Output format: json, list
+    /// Output format: json, jsonl, list, org, markdown, sarif, html, vimgrep, errorformat, csv, tsv
     #[arg(short = 'O', long = "output-format")]
     pub(crate) output_format: Option<String>,
 
@@ -58,11 +81,171 @@ pub struct Args {
     /// Show content around the crumb for giving more context
     #[arg(short, long, default_value = "0")]
     pub(crate) range: u32,
+
+    /// Group the crumbs by "file" or "keyword" in markdown output, default is file
+    #[arg(long = "group-by")]
+    pub(crate) group_by: Option<String>,
+
+    /// How the crumb lines are grouped to one crumb: explicit (default, the line
+    /// after "..."), consecutive (all lines next to each other), or indent (the
+    /// line indented more than the first line)
+    #[arg(long = "group-mode")]
+    pub(crate) group_mode: Option<String>,
+
+    /// Severity of keyword like FIXME=error, used by errorformat and sarif output.
+    /// Default: FIXME=error, TODO=warning, MARK=info
+    #[arg(long = "severity", value_name = "KEYWORD=SEVERITY")]
+    pub(crate) severities: Vec<String>,
+
+    /// Define the keyword with its aliases, severity, color and description, like
+    /// "FIXME,fix,bug=error:red:must be fixed before release". The keywords
+    /// matching the name or aliases are shown as the name
+    #[arg(
+        long = "keyword-def",
+        value_name = "NAME[,ALIAS...]=SEVERITY[:COLOR[:DESCRIPTION]]"
+    )]
+    pub(crate) keyword_defs: Vec<String>,
+
+    /// Only the crumbs at or above the severity: info, warning or error
+    #[arg(long = "min-severity")]
+    pub(crate) min_severity: Option<String>,
+
+    /// Exit with error if any crumb (not ignored) is at or above the severity
+    #[arg(long = "fail-on")]
+    pub(crate) fail_on: Option<String>,
+
+    /// Comma separated columns of csv/tsv output, default is
+    /// path,cell,line,column,keyword,text,ignored,tails (and author,email,commit,date with --blame)
+    #[arg(long = "columns")]
+    pub(crate) columns: Option<String>,
+
+    /// Output template of every crumb, like '{path}:{line}:{col} [{keyword}] {text}'.
+    /// It overrides the output format
+    #[arg(long = "template")]
+    pub(crate) template: Option<String>,
+
+    /// Output template file with [[header]], [[file_header]], [[crumb]], [[context]],
+    /// [[file_footer]] and [[footer]] sections. It overrides the output format
+    #[arg(long = "template-file")]
+    pub(crate) template_file: Option<String>,
+
+    /// Print the summary statistics instead of crumbs, json with "-O json"
+    #[arg(long = "stats")]
+    pub(crate) stats: bool,
+
+    /// Add the author, email, commit and date of crumbs by git blame
+    #[arg(long = "blame")]
+    pub(crate) blame: bool,
+
+    /// Only the crumbs older than the duration like 90d, 2w, 6m, 1y (needs git blame)
+    #[arg(long = "older-than")]
+    pub(crate) older_than: Option<String>,
+
+    /// Only the crumbs of this author name or email (needs git blame)
+    #[arg(long = "author")]
+    pub(crate) authors: Vec<String>,
+
+    /// Sort the crumbs by: age (the oldest first, needs git blame),
+    /// severity (the most severe first)
+    #[arg(long = "sort")]
+    pub(crate) sort: Option<String>,
+
+    /// Keep watching the files/dirs, print the changed crumbs again
+    /// (or add/remove events with "-O jsonl") when files changed
+    #[arg(long = "watch")]
+    pub(crate) watch: bool,
+
+    /// Delete/restore the crumbs without prompt
+    #[arg(short = 'y', long = "yes")]
+    pub(crate) yes: bool,
+
+    /// Never prompt when delete/restore, only print what would change
+    /// unless "--yes" is given too
+    #[arg(long = "no-input")]
+    pub(crate) no_input: bool,
+
+    /// Only delete/restore the crumbs in this file or dir
+    #[arg(long = "select-file", value_name = "PATH")]
+    pub(crate) select_files: Vec<String>,
+
+    /// Only delete/restore the crumbs in the line range, like 10-20, 10- or 10
+    #[arg(long = "select-lines", value_name = "START-END")]
+    pub(crate) select_lines: Option<String>,
+
+    /// Only delete/restore the crumb of this id (path:line)
+    #[arg(long = "select-id", value_name = "ID")]
+    pub(crate) select_ids: Vec<String>,
+
+    /// Only delete/restore the crumbs whose content matches the regex
+    #[arg(long = "select-regex", value_name = "REGEX")]
+    pub(crate) select_regex: Option<String>,
+
+    /// Only delete/restore the ignored crumbs (true) or not ignored crumbs (false)
+    #[arg(long = "select-ignored", value_name = "BOOL")]
+    pub(crate) select_ignored: Option<bool>,
+
+    /// Triage the crumbs in the full-screen terminal UI, mark them to
+    /// delete or restore, then apply all of them after confirmation
+    #[arg(long = "tui")]
+    pub(crate) tui: bool,
+
+    /// Cache the scan result in the file (default .codeitlater-cache),
+    /// only the changed files are parsed again
+    #[arg(long = "cache", value_name = "CACHE_FILE", num_args = 0..=1, default_missing_value = crate::cache::DEFAULT_CACHE_FILE)]
+    pub(crate) cache: Option<String>,
+
+    /// Also scan the plain keyword comments without ":=", like "// TODO: here".
+    /// The keywords are given by "-k", or TODO/FIXME/HACK/XXX/BUG by default
+    #[arg(long = "compat")]
+    pub(crate) compat: bool,
+
+    /// Also scan the ".txt" files, the line starting with ":=" is the crumb
+    #[arg(long = "txt")]
+    pub(crate) txt: bool,
+
+    #[command(subcommand)]
+    pub(crate) command: Option<Command>,
+}
+
+/// Subcommands running codeitlater as the other service or action rather than scanning once
+#[derive(Subcommand, Debug, Clone, PartialEq)]
+pub enum Command {
+    /// Run the language server speaking LSP over stdio
+    Lsp,
+    /// Run the Model Context Protocol server over stdio for coding agents
+    Mcp,
+    /// Rewrite the plain keyword comments to crumbs, with the same prompt
+    /// (or "--yes"/"--no-input") as delete
+    Adopt {
+        /// What are the files/dirs to adopt, instead of the targets before "adopt"
+        #[arg(value_name = "files/dirs")]
+        targets: Vec<String>,
+    },
+    /// Add the crumb at the line of file with the comment symbol of its language,
+    /// as the new line above it with the same indentation
+    Add {
+        /// Where to add the crumb, like src/main.rs:42
+        #[arg(value_name = "path:line")]
+        location: String,
+        /// The content of crumb, like "TODO: handle timeout"
+        text: String,
+        /// Append the crumb to the end of the line rather than adding the new line
+        #[arg(long = "append")]
+        append: bool,
+    },
+    /// Apply the edited json document of "-O json" back to the files: the changed
+    /// keyword and view_content, and removing the crumbs marked "remove": true
+    Apply {
+        /// The json document file, read from stdin if it is "-" or not given
+        #[arg(value_name = "JSON_FILE")]
+        file: Option<String>,
+    },
 }
 
 impl Args {
     /// cover this args with other, self values totally rewrotten by other
-    /// if both of args have same fields. Except ignore dirs, they are merged
+    /// if both of args have same fields. Except ignore dirs, severities and keyword
+    /// definitions, they are merged
     pub fn cover(&mut self, mut other: Self) {
         if other.filetypes.len() != 0 {
             self.filetypes = other.filetypes
@@ -76,6 +259,22 @@ impl Args {
             self.keywords = other.keywords
         }
 
+        if other.keyword_mode.is_some() {
+            self.keyword_mode = other.keyword_mode
+        }
+
+        if other.case_sensitive {
+            self.case_sensitive = other.case_sensitive
+        }
+
+        if !other.exclude_keywords.is_empty() {
+            self.exclude_keywords = other.exclude_keywords
+        }
+
+        if other.no_keyword {
+            self.no_keyword = other.no_keyword
+        }
+
         if other.jsonx.is_some() {
             self.jsonx = other.jsonx
         }
@@ -92,6 +291,78 @@ impl Args {
             self.restore = other.restore
         }
 
+        if other.blame {
+            self.blame = other.blame
+        }
+
+        if other.older_than.is_some() {
+            self.older_than = other.older_than
+        }
+
+        if !other.authors.is_empty() {
+            self.authors = other.authors
+        }
+
+        if other.sort.is_some() {
+            self.sort = other.sort
+        }
+
+        if other.command.is_some() {
+            self.command = other.command
+        }
+
+        if other.cache.is_some() {
+            self.cache = other.cache
+        }
+
+        if other.compat {
+            self.compat = other.compat
+        }
+
+        if other.txt {
+            self.txt = other.txt
+        }
+
+        if other.watch {
+            self.watch = other.watch
+        }
+
+        if other.tui {
+            self.tui = other.tui
+        }
+
+        if other.yes {
+            self.yes = other.yes
+        }
+
+        if other.no_input {
+            self.no_input = other.no_input
+        }
+
+        if !other.select_files.is_empty() {
+            self.select_files = other.select_files
+        }
+
+        if other.select_lines.is_some() {
+            self.select_lines = other.select_lines
+        }
+
+        if !other.select_ids.is_empty() {
+            self.select_ids = other.select_ids
+        }
+
+        if other.select_regex.is_some() {
+            self.select_regex = other.select_regex
+        }
+
+        if other.select_ignored.is_some() {
+            self.select_ignored = other.select_ignored
+        }
+
+        if other.stats {
+            self.stats = other.stats
+        }
+
         if other.fmt_command.is_some() {
             self.fmt_command = other.fmt_command
         }
@@ -100,6 +371,39 @@ impl Args {
             self.output_format = other.output_format
         }
 
+        if !other.severities.is_empty() {
+            self.severities.append(&mut other.severities)
+        }
+
+        if !other.keyword_defs.is_empty() {
+            self.keyword_defs.append(&mut other.keyword_defs)
+        }
+
+        if other.min_severity.is_some() {
+            self.min_severity = other.min_severity
+        }
+
+        if other.fail_on.is_some() {
+            self.fail_on = other.fail_on
+        }
+
+        if other.template.is_some() || other.template_file.is_some() {
+            self.template = other.template;
+            self.template_file = other.template_file
+        }
+
+        if other.columns.is_some() {
+            self.columns = other.columns
+        }
+
+        if other.group_by.is_some() {
+            self.group_by = other.group_by
+        }
+
+        if other.group_mode.is_some() {
+            self.group_mode = other.group_mode
+        }
+
         self.show_ignore = other.show_ignore;
 
         self.range = other.range
@@ -240,6 +544,30 @@ mod tests {
         assert_eq!(args.restore, true);
     }
 
+    #[test]
+    fn test_parse_subcommand() {
+        let args = Args::parse_from(vec!["codeitlater", "-k", "TODO", "lsp"]);
+        assert_eq!(args.command, Some(Command::Lsp));
+        assert_eq!(args.targets, vec![".".to_string()]);
+
+        let args = Args::parse_from(vec!["codeitlater", "src", "tests", "lsp"]);
+        assert_eq!(args.command, Some(Command::Lsp));
+        assert_eq!(args.targets, vec!["src".to_string(), "tests".to_string()]);
+
+        let args = Args::parse_from(vec!["codeitlater", "-y", "adopt", "src", "tests"]);
+        assert_eq!(
+            args.command,
+            Some(Command::Adopt {
+                targets: vec!["src".to_string(), "tests".to_string()]
+            })
+        );
+
+        // the dir has the same name as subcommand
+        let args = Args::parse_from(vec!["codeitlater", "./lsp"]);
+        assert_eq!(args.command, None);
+        assert_eq!(args.targets, vec!["./lsp".to_string()]);
+    }
+
     #[test]
     fn test_read_current_path_config() {
         let content = "
@@ -0,0 +1,176 @@
+//! Enrich the crumbs with local `git blame`
+
+use super::datatypes::*;
+use log::debug;
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// run `git blame --porcelain` of this file, return the blame of every line
+fn git_blame(file_path: &str) -> Result<HashMap<usize, Blame>, String> {
+    let path = Path::new(file_path);
+    let dir = match path.parent() {
+        Some(d) if !d.as_os_str().is_empty() => d,
+        _ => Path::new("."),
+    };
+    let file_name = path
+        .file_name()
+        .ok_or(format!("{} is not a file", file_path))?;
+
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["blame", "--porcelain", "--"])
+        .arg(file_name)
+        .output()
+        .map_err(|e| e.to_string())?;
+
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).to_string());
+    }
+
+    Ok(parse_porcelain(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// parse the output of `git blame --porcelain`. The commit information only
+/// shows the first time the commit shows up
+fn parse_porcelain(content: &str) -> HashMap<usize, Blame> {
+    let mut commits: HashMap<&str, Blame> = HashMap::new();
+    let mut result = HashMap::new();
+    let mut current: Option<(&str, usize)> = None;
+
+    for line in content.lines() {
+        if line.starts_with('\t') {
+            // the content line ends this line's blame
+            if let Some((commit, line_num)) = current.take() {
+                let mut b = commits.get(commit).cloned().unwrap_or_default();
+                b.commit = commit.to_string();
+                result.insert(line_num, b);
+            }
+            continue;
+        }
+
+        let mut words = line.splitn(2, ' ');
+        let (key, value) = (words.next().unwrap_or(""), words.next().unwrap_or(""));
+        if current.is_none() {
+            // header line: <commit> <original line> <final line> [<lines count>]
+            let final_line = value.split(' ').nth(1).and_then(|n| n.parse().ok());
+            if let Some(n) = final_line
+                && key.len() >= 40
+                && key.bytes().all(|b| b.is_ascii_hexdigit())
+            {
+                current = Some((key, n));
+                commits.entry(key).or_default();
+            }
+            continue;
+        }
+
+        let commit = commits.get_mut(current.unwrap().0).unwrap();
+        match key {
+            "author" => commit.author = value.to_string(),
+            "author-mail" => {
+                commit.email = value.trim_start_matches('<').trim_end_matches('>').to_string()
+            }
+            "author-time" => commit.timestamp = value.parse().unwrap_or_default(),
+            _ => (),
+        }
+    }
+
+    result
+}
+
+/// add the blame to all crumbs of this bread. Bread keeps same if git blame failed
+pub fn blame_bread(bread: &mut Bread) {
+    match git_blame(&bread.file_path) {
+        Ok(table) => bread
+            .crumbs
+            .iter_mut()
+            .for_each(|c| c.set_blames(|ln| table.get(&ln).cloned())),
+        Err(e) => debug!("cannot git blame {}: {}", bread.file_path, e),
+    }
+}
+
+/// parse the duration like `90d`, `2w`, `6m`, `1y`, `12h` to seconds
+pub fn parse_duration(s: &str) -> Result<i64, String> {
+    let s = s.trim();
+    let unit = s
+        .chars()
+        .last()
+        .ok_or("duration cannot be empty".to_string())?;
+    let seconds = match unit {
+        'h' => 3600,
+        'd' => 86400,
+        'w' => 7 * 86400,
+        'm' => 30 * 86400,
+        'y' => 365 * 86400,
+        _ => return Err(format!("unknown unit of duration {}, should be h/d/w/m/y", s)),
+    };
+    let n: i64 = s[..s.len() - 1]
+        .parse()
+        .map_err(|_| format!("cannot parse duration {}", s))?;
+    Ok(n * seconds)
+}
+
+/// the unix timestamp now
+pub fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_porcelain() {
+        let content = "15a03893f06c7182971ee173877248c80976b35b 1 1 2
+author Alice
+author-mail <alice@example.com>
+author-time 1700000000
+author-tz +0000
+committer Bob
+committer-mail <bob@example.com>
+committer-time 1700000001
+committer-tz +0000
+summary first
+filename a.rs
+\t//:= TODO: a
+15a03893f06c7182971ee173877248c80976b35b 2 2
+\tfn main() {}
+0000000000000000000000000000000000000000 3 3 1
+author Not Committed Yet
+author-mail <not.committed.yet>
+author-time 1800000000
+author-tz +0000
+summary Version of a.rs from a.rs
+filename a.rs
+\t//:= author 1.2
+";
+        let table = parse_porcelain(content);
+        assert_eq!(table.len(), 3);
+        assert_eq!(
+            table.get(&2).unwrap(),
+            &Blame {
+                author: "Alice".to_string(),
+                email: "alice@example.com".to_string(),
+                commit: "15a03893f06c7182971ee173877248c80976b35b".to_string(),
+                timestamp: 1700000000,
+            }
+        );
+        assert_eq!(table.get(&3).unwrap().author, "Not Committed Yet");
+        assert_eq!(table.get(&3).unwrap().timestamp, 1800000000);
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("90d"), Ok(90 * 86400));
+        assert_eq!(parse_duration("2w"), Ok(14 * 86400));
+        assert_eq!(parse_duration("12h"), Ok(12 * 3600));
+        assert!(parse_duration("90").is_err());
+        assert!(parse_duration("d").is_err());
+        assert!(parse_duration("").is_err());
+    }
+}
@@ -0,0 +1,215 @@
+//! The on-disk cache of breads, so the unchanged files don't need to parse again
+//!
+//! The entry of every file is keyed by its path, size, modified time and the
+//! hash of its content. The whole cache is dropped when anything changing the
+//! parsing result (the languages table, keywords, config) is different.
+
+use super::config::{COMPAT_REGEX_TABLE, Config, KEYWORDS_REGEX, REGEX_TABLE};
+use super::datatypes::Bread;
+use log::debug;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, Metadata};
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex};
+use std::time::UNIX_EPOCH;
+
+/// bump it when the cache file format or `Bread` changed
+const CACHE_VERSION: u32 = 2;
+
+/// default cache file name
+pub const DEFAULT_CACHE_FILE: &str = ".codeitlater-cache";
+
+static CACHE: LazyLock<Mutex<Option<Cache>>> = LazyLock::new(|| Mutex::new(None));
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CacheFile {
+    version: u32,
+    fingerprint: String,
+    entries: HashMap<String, CacheEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CacheEntry {
+    size: u64,
+    /// modified time in (seconds, nanoseconds)
+    mtime: (u64, u32),
+    hash: u64,
+    bread: Option<Bread>,
+}
+
+#[derive(Debug)]
+struct Cache {
+    path: PathBuf,
+    fingerprint: String,
+    entries: HashMap<String, CacheEntry>,
+    dirty: bool,
+}
+
+/// FNV-1a hash, it is stable between rust versions, unlike the DefaultHasher
+pub(crate) fn content_hash(content: &[u8]) -> u64 {
+    content.iter().fold(0xcbf29ce484222325, |h, b| {
+        (h ^ *b as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// the fingerprint of everything changing the result of parsing files
+fn fingerprint(conf: &Config) -> String {
+    let mut languages = REGEX_TABLE
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(k, v)| format!("{}={}", k, v.as_str()))
+        .collect::<Vec<_>>();
+    if conf.compat {
+        languages.extend(
+            COMPAT_REGEX_TABLE
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|(k, v)| format!("compat {}={}", k, v.as_str())),
+        );
+    }
+    languages.sort();
+
+    let keywords = KEYWORDS_REGEX
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map(|re| re.as_str().to_string())
+        .unwrap_or_default();
+
+    let all = format!(
+        "{}\n{}\n{}\n{}\n{:?}\n{:?}\n{}\n{}\n{}\n{:?}",
+        CACHE_VERSION,
+        env!("CARGO_PKG_VERSION"),
+        languages.join("\n"),
+        keywords,
+        // the keywords are normalized by their definitions
+        conf.keywords,
+        conf.defined_keywords.as_ref().map(|re| re.as_str()),
+        conf.show_ignored,
+        conf.range,
+        conf.no_keyword,
+        conf.group_mode,
+    );
+    format!("{:016x}", content_hash(all.as_bytes()))
+}
+
+fn mtime(meta: &Metadata) -> (u64, u32) {
+    meta.modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| (d.as_secs(), d.subsec_nanos()))
+        .unwrap_or_default()
+}
+
+/// load the cache file if the config uses cache. The cache is empty if the
+/// cache file doesn't exist, is broken, or its fingerprint is different
+pub(crate) fn load(conf: &Config) {
+    let mut cache = CACHE.lock().unwrap();
+    let Some(path) = &conf.cache else {
+        *cache = None;
+        return;
+    };
+
+    let fingerprint = fingerprint(conf);
+    let entries = fs::read(path)
+        .ok()
+        .and_then(|content| serde_json::from_slice::<CacheFile>(&content).ok())
+        .filter(|f| f.version == CACHE_VERSION && f.fingerprint == fingerprint)
+        .map(|f| f.entries)
+        .unwrap_or_default();
+    debug!("load {} entries from cache {}", entries.len(), path);
+
+    *cache = Some(Cache {
+        path: PathBuf::from(path),
+        fingerprint,
+        entries,
+        dirty: false,
+    })
+}
+
+/// the bread of this file if the size and modified time are same
+pub(crate) fn lookup(file_path: &str, meta: &Metadata) -> Option<Option<Bread>> {
+    let cache = CACHE.lock().unwrap();
+    let entry = cache.as_ref()?.entries.get(file_path)?;
+    if entry.size == meta.len() && entry.mtime == mtime(meta) {
+        Some(entry.bread.clone())
+    } else {
+        None
+    }
+}
+
+/// the bread of this file if the content is same, the modified time is updated
+pub(crate) fn lookup_by_hash(file_path: &str, meta: &Metadata, hash: u64) -> Option<Option<Bread>> {
+    let mut cache = CACHE.lock().unwrap();
+    let cache = cache.as_mut()?;
+    let entry = cache.entries.get_mut(file_path)?;
+    if entry.size == meta.len() && entry.hash == hash {
+        entry.mtime = mtime(meta);
+        cache.dirty = true;
+        Some(entry.bread.clone())
+    } else {
+        None
+    }
+}
+
+pub(crate) fn store(file_path: &str, meta: &Metadata, hash: u64, bread: &Option<Bread>) {
+    let mut cache = CACHE.lock().unwrap();
+    if let Some(cache) = cache.as_mut() {
+        cache.entries.insert(
+            file_path.to_string(),
+            CacheEntry {
+                size: meta.len(),
+                mtime: mtime(meta),
+                hash,
+                bread: bread.clone(),
+            },
+        );
+        cache.dirty = true;
+    }
+}
+
+/// write the cache back to file if anything changed, and drop the entries
+/// of those files not existing anymore
+pub(crate) fn save() {
+    let mut cache = CACHE.lock().unwrap();
+    let Some(cache) = cache.as_mut() else {
+        return;
+    };
+
+    let before = cache.entries.len();
+    cache.entries.retain(|f, _| Path::new(f).exists());
+    if !cache.dirty && before == cache.entries.len() {
+        return;
+    }
+
+    let file = CacheFile {
+        version: CACHE_VERSION,
+        fingerprint: cache.fingerprint.clone(),
+        entries: std::mem::take(&mut cache.entries),
+    };
+    match serde_json::to_vec(&file) {
+        Ok(content) => {
+            if let Err(e) = fs::write(&cache.path, content) {
+                debug!("cannot write cache {}: {}", cache.path.display(), e)
+            }
+        }
+        Err(e) => debug!("cannot serialize cache: {}", e),
+    }
+    cache.entries = file.entries;
+    cache.dirty = false;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_content_hash() {
+        assert_eq!(content_hash(b""), 0xcbf29ce484222325);
+        assert_eq!(content_hash(b"a"), 0xaf63dc4c8601ec8c);
+        assert_ne!(content_hash(b"ab"), content_hash(b"ba"));
+    }
+}
@@ -1,12 +1,16 @@
-use regex::{Regex, RegexBuilder};
+use regex::Regex;
 use std::collections::HashMap;
 use std::ffi::OsString;
 
 use std::fs::File;
 use std::io::Read;
+use std::str::FromStr;
 use std::sync::{LazyLock, Mutex};
 
-use super::args::Args;
+use super::args::{Args, Command};
+use super::blame;
+use super::datatypes::{Bread, Crumb, markdown_link_path};
+use super::template::Template;
 
 /// Inner dictionary
 const DICT: &'static str = r#"
@@ -20,10 +24,62 @@ const DICT: &'static str = r#"
 "hs":["-- "],
 "el":[";"],
 "clj":[";"],
-"js":["//"]
+"js":["//"],
+"html":["<!--"],
+"htm":["<!--"],
+"xml":["<!--"],
+"md":["<!--"],
+"markdown":["<!--"],
+"ipynb":["\\#"]
 }
 "#;
 
+/// the language names and their keys in table. The first key of the name
+/// is the language of that name
+const LANGUAGE_NAMES: [(&str, &str); 17] = [
+    ("rust", "rs"),
+    ("go", "go"),
+    ("common-lisp", "lisp"),
+    ("common-lisp", "asd"),
+    ("common-lisp", "asdf"),
+    ("python", "py"),
+    ("haskell", "hs"),
+    ("emacs-lisp", "el"),
+    ("clojure", "clj"),
+    ("javascript", "js"),
+    ("html", "html"),
+    ("html", "htm"),
+    ("xml", "xml"),
+    ("markdown", "md"),
+    ("markdown", "markdown"),
+    ("text", "txt"),
+    ("jupyter", "ipynb"),
+];
+
+/// the language name of the key in table, like `rust` of `rs`
+pub(crate) fn language_name(key: &str) -> Option<&'static str> {
+    LANGUAGE_NAMES
+        .iter()
+        .find(|(_, k)| *k == key)
+        .map(|(name, _)| *name)
+}
+
+/// the key in table of the language name, like `rs` of `rust`
+pub(crate) fn language_key(name: &str) -> Option<&'static str> {
+    LANGUAGE_NAMES
+        .iter()
+        .find(|(n, _)| *n == name)
+        .map(|(_, key)| *key)
+}
+
+/// the block comment symbols and their closers, the closer isn't the part
+/// of crumb content
+const BLOCK_COMMENTS: [(&str, &str); 1] = [("<!--", "-->")];
+
+/// the regex of plain text files in txt mode, the line starting with `:=`
+/// is the crumb and its indentation is the header
+const TXT_REGEX: &str = r"^([ \t]*):=\s+(.*)";
+
 static TABLE: LazyLock<Mutex<HashMap<String, Vec<String>>>> =
     LazyLock::new(|| Mutex::new(serde_json::from_str(DICT).unwrap()));
 
@@ -38,6 +94,14 @@ pub static REGEX_TABLE: LazyLock<Mutex<HashMap<String, Regex>>> = LazyLock::new(
 
 pub static KEYWORDS_REGEX: LazyLock<Mutex<Option<Regex>>> = LazyLock::new(|| Mutex::new(None));
 
+/// the keywords of the plain comments in compat mode if keywords aren't given
+const COMPAT_KEYWORDS: [&str; 5] = ["TODO", "FIXME", "HACK", "XXX", "BUG"];
+
+/// the regex table of the plain keyword comments (without `:=`),
+/// only filled when compat mode is on
+pub static COMPAT_REGEX_TABLE: LazyLock<Mutex<HashMap<String, Regex>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
 /// Update static table with new raw_json str
 fn update_table(raw_json: &str) {
     let new_table: HashMap<String, Vec<String>> = serde_json::from_str(raw_json).unwrap();
@@ -56,8 +120,60 @@ fn update_table(raw_json: &str) {
         });
 }
 
+/// the closer of the block comment starting with its symbol, like `-->`
+/// of `<!--`
+pub(crate) fn comment_closer(comment: &str) -> Option<&'static str> {
+    BLOCK_COMMENTS
+        .iter()
+        .find(|(open, _)| comment.starts_with(open))
+        .map(|(_, close)| *close)
+}
+
+/// the content part of regex, which stops at the closer if the language
+/// has the block comment symbol
+fn make_content(com_syms: &[String]) -> String {
+    match com_syms.iter().find_map(|s| comment_closer(s)) {
+        Some(c) => format!("(.*?)\\s*(?:{}|$)", regex::escape(c)),
+        None => "(.*)".to_string(),
+    }
+}
+
+/// scan the `.txt` files in txt mode, or use the txt language in table
+fn update_txt_regex(txt: bool) {
+    let regex = if txt {
+        Some(TXT_REGEX.to_string())
+    } else {
+        TABLE.lock().unwrap().get("txt").map(make_regex)
+    };
+    let mut re_table = REGEX_TABLE.lock().unwrap();
+    match regex {
+        Some(r) => re_table.insert("txt".to_string(), Regex::new(&r).unwrap()),
+        None => re_table.remove("txt"),
+    };
+}
+
+/// the first comment symbol of the language in table without the regex
+/// escaping, like `#` of `\#`
+pub(crate) fn comment_symbol(language: &str) -> Option<String> {
+    let table = TABLE.lock().unwrap();
+    let mut chars = table.get(language)?.first()?.chars();
+    let mut symbol = String::new();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => symbol.extend(chars.next()),
+            c => symbol.push(c),
+        }
+    }
+    Some(symbol)
+}
+
 /// Making regex string
 fn make_regex(com_syms: &Vec<String>) -> String {
+    format!("({}):=\\s+{}", make_head(com_syms), make_content(com_syms))
+}
+
+/// Making the comment symbols part of regex
+fn make_head(com_syms: &Vec<String>) -> String {
     let mut head = String::new();
     for s in com_syms {
         head.push('|');
@@ -66,12 +182,48 @@ fn make_regex(com_syms: &Vec<String>) -> String {
     }
 
     let _ = head.drain(..1).collect::<String>();
+    head
+}
 
-    format!("({}):=\\s+(.*)", head)
+/// Making the regex of plain keyword comment, like `// TODO: here` or
+/// `# FIXME here`. The keywords given are case insensitive as the keywords filter
+/// unless `case_sensitive`
+fn make_compat_regex(
+    com_syms: &Vec<String>,
+    keywords: Option<&Vec<String>>,
+    case_sensitive: bool,
+) -> String {
+    let keywords = match keywords {
+        Some(kk) if case_sensitive => format!("(?:{})", kk.join("|")),
+        Some(kk) => format!("(?i:{})", kk.join("|")),
+        None => COMPAT_KEYWORDS.join("|"),
+    };
+    format!(
+        "({})\\s*({})\\b:?\\s*{}",
+        make_head(com_syms),
+        keywords,
+        make_content(com_syms)
+    )
 }
 
-/// making the keyword regex, case insensitive
-pub(super) fn make_key_regex(keywords: &Vec<String>) {
+/// fill the compat regex table by the languages table
+fn update_compat_table(keywords: Option<&Vec<String>>, case_sensitive: bool) {
+    let table = TABLE.lock().unwrap();
+    let mut compat_table = COMPAT_REGEX_TABLE.lock().unwrap();
+    *compat_table = table
+        .iter()
+        .map(|(k, v)| {
+            (
+                k.clone(),
+                Regex::new(&make_compat_regex(v, keywords, case_sensitive)).unwrap(),
+            )
+        })
+        .collect();
+}
+
+/// making the keyword regex from the keyword patterns, case insensitive
+/// unless `case_sensitive`
+pub(super) fn make_key_regex(keywords: &Vec<String>, case_sensitive: bool) {
     let mut ss = String::new();
     for s in keywords {
         ss.push_str(&s);
@@ -80,12 +232,8 @@ pub(super) fn make_key_regex(keywords: &Vec<String>) {
 
     let _ = ss.drain(ss.len() - 1..).collect::<String>();
     let mut kk = KEYWORDS_REGEX.lock().unwrap();
-    *kk = Some(
-        RegexBuilder::new(&format!("({}):\\s*(.*)", ss))
-            .case_insensitive(true)
-            .build()
-            .unwrap(),
-    );
+    let flag = if case_sensitive { "" } else { "(?i)" };
+    *kk = Some(Regex::new(&format!("{}({}):\\s*(.*)", flag, ss)).unwrap());
 }
 
 pub fn clean_keywords_table() {
@@ -97,8 +245,18 @@ pub fn clean_keywords_table() {
 pub(super) enum OutputFormat {
     None,
     Json,
+    Jsonl,
     List,
     Range,
+    Org,
+    Markdown,
+    Sarif,
+    Html,
+    Vimgrep,
+    Errorformat,
+    Csv,
+    Tsv,
+    Template(Template),
 }
 
 impl Default for OutputFormat {
@@ -107,6 +265,571 @@ impl Default for OutputFormat {
     }
 }
 
+/// severity of the crumb, used by editor and code-scanning outputs
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+
+    /// the level name in SARIF
+    pub fn sarif_level(&self) -> &'static str {
+        match self {
+            Severity::Info => "note",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+
+    /// the DiagnosticSeverity number in LSP
+    pub fn lsp_severity(&self) -> u8 {
+        match self {
+            Severity::Info => 3,
+            Severity::Warning => 2,
+            Severity::Error => 1,
+        }
+    }
+}
+
+impl FromStr for Severity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "info" | "note" => Ok(Severity::Info),
+            "warning" | "warn" => Ok(Severity::Warning),
+            "error" => Ok(Severity::Error),
+            _ => Err(format!("unknown severity {}, should be error/warning/info", s)),
+        }
+    }
+}
+
+/// the keyword to severity table, keyword is case insensitive.
+/// crumbs without keyword or keyword not in table are info
+#[derive(Clone, Debug)]
+pub struct SeverityTable(HashMap<String, Severity>);
+
+impl Default for SeverityTable {
+    fn default() -> Self {
+        Self(HashMap::from([
+            ("FIXME".to_string(), Severity::Error),
+            ("TODO".to_string(), Severity::Warning),
+            ("MARK".to_string(), Severity::Info),
+        ]))
+    }
+}
+
+impl SeverityTable {
+    pub fn get(&self, keyword: Option<&str>) -> Severity {
+        keyword
+            .and_then(|k| self.0.get(&k.to_uppercase()))
+            .copied()
+            .unwrap_or(Severity::Info)
+    }
+
+    /// insert the `KEYWORD=severity` pair
+    fn insert_pair(&mut self, pair: &str) -> Result<(), String> {
+        let (k, v) = pair
+            .split_once('=')
+            .ok_or(format!("severity {} should be KEYWORD=severity", pair))?;
+        self.0.insert(k.to_uppercase(), v.parse()?);
+        Ok(())
+    }
+
+    fn insert(&mut self, keyword: &str, severity: Severity) {
+        self.0.insert(keyword.to_uppercase(), severity);
+    }
+}
+
+/// the terminal color of keyword
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Color {
+    Black,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+}
+
+impl Color {
+    /// the ANSI foreground color code
+    fn ansi_code(&self) -> u8 {
+        30 + *self as u8
+    }
+}
+
+impl FromStr for Color {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "black" => Ok(Color::Black),
+            "red" => Ok(Color::Red),
+            "green" => Ok(Color::Green),
+            "yellow" => Ok(Color::Yellow),
+            "blue" => Ok(Color::Blue),
+            "magenta" => Ok(Color::Magenta),
+            "cyan" => Ok(Color::Cyan),
+            "white" => Ok(Color::White),
+            _ => Err(format!(
+                "unknown color {}, should be black/red/green/yellow/blue/magenta/cyan/white",
+                s
+            )),
+        }
+    }
+}
+
+/// how the keywords given by `-k` and `--exclude-keyword` match
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum KeywordMode {
+    /// the keyword as it is
+    #[default]
+    Literal,
+    Regex,
+    /// `*` matches any characters and `?` matches one character in keyword
+    Glob,
+}
+
+impl KeywordMode {
+    /// the regex pattern of keyword in this mode
+    fn pattern(&self, keyword: &str) -> String {
+        match self {
+            KeywordMode::Literal => regex::escape(keyword),
+            KeywordMode::Regex => format!("(?:{})", keyword),
+            KeywordMode::Glob => keyword
+                .chars()
+                .map(|c| match c {
+                    // the keyword never has spaces or colons
+                    '*' => "[^:\\s]*".to_string(),
+                    '?' => "[^:\\s]".to_string(),
+                    c => regex::escape(&c.to_string()),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl FromStr for KeywordMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "literal" => Ok(KeywordMode::Literal),
+            "regex" => Ok(KeywordMode::Regex),
+            "glob" => Ok(KeywordMode::Glob),
+            _ => Err(format!(
+                "unknown keyword mode {}, should be literal/regex/glob",
+                s
+            )),
+        }
+    }
+}
+
+/// the keyword defined by `--keyword-def`
+#[derive(Clone, Debug, PartialEq)]
+pub struct KeywordDef {
+    /// the canonical name
+    pub name: String,
+    pub aliases: Vec<String>,
+    pub severity: Option<Severity>,
+    pub color: Option<Color>,
+    pub description: Option<String>,
+}
+
+impl KeywordDef {
+    /// the keyword is the name or one of aliases, case insensitive
+    fn is(&self, keyword: &str) -> bool {
+        self.name.eq_ignore_ascii_case(keyword)
+            || self.aliases.iter().any(|a| a.eq_ignore_ascii_case(keyword))
+    }
+}
+
+/// parse the `NAME[,ALIAS...]=SEVERITY[:COLOR[:DESCRIPTION]]`,
+/// the empty severity or color is not given
+impl FromStr for KeywordDef {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || {
+            format!(
+                "keyword definition {} should be NAME[,ALIAS...]=SEVERITY[:COLOR[:DESCRIPTION]]",
+                s
+            )
+        };
+        let (names, rest) = s.split_once('=').ok_or_else(err)?;
+        let mut names = names
+            .split(',')
+            .map(|n| n.trim())
+            .filter(|n| !n.is_empty())
+            .map(|n| n.to_string());
+        let name = names.next().ok_or_else(err)?;
+
+        let mut parts = rest
+            .splitn(3, ':')
+            .map(|p| p.trim())
+            .map(|p| Some(p).filter(|p| !p.is_empty()));
+        Ok(Self {
+            name,
+            aliases: names.collect(),
+            severity: parts.next().flatten().map(|p| p.parse()).transpose()?,
+            color: parts.next().flatten().map(|p| p.parse()).transpose()?,
+            description: parts.next().flatten().map(|p| p.to_string()),
+        })
+    }
+}
+
+/// the registry of keywords, the crumb keyword is normalized to the canonical
+/// name of the definition it matches. The keywords given by `-k` are in it too
+#[derive(Clone, Debug, Default)]
+pub struct KeywordRegistry(Vec<KeywordDef>);
+
+impl KeywordRegistry {
+    /// the definition of keyword by its name or alias
+    pub fn get(&self, keyword: &str) -> Option<&KeywordDef> {
+        self.0.iter().find(|d| d.is(keyword))
+    }
+
+    /// the canonical name of keyword, or itself if it isn't in registry
+    pub fn canonical<'a>(&'a self, keyword: &'a str) -> &'a str {
+        self.get(keyword).map_or(keyword, |d| d.name.as_str())
+    }
+
+    pub fn description(&self, keyword: &str) -> Option<&str> {
+        self.get(keyword).and_then(|d| d.description.as_deref())
+    }
+
+    /// the keyword in its color for terminal, itself if it doesn't have color
+    pub fn paint(&self, keyword: &str) -> String {
+        match self.get(keyword).and_then(|d| d.color) {
+            Some(c) => format!("\x1b[{}m{}\x1b[0m", c.ansi_code(), keyword),
+            None => keyword.to_string(),
+        }
+    }
+
+    /// define the keyword, the one with same name is replaced
+    fn define(&mut self, def: KeywordDef) {
+        match self
+            .0
+            .iter_mut()
+            .find(|d| d.name.eq_ignore_ascii_case(&def.name))
+        {
+            Some(d) => *d = def,
+            None => self.0.push(def),
+        }
+    }
+
+    /// add the keyword given by `-k` if it isn't defined yet
+    fn add_keyword(&mut self, keyword: &str) {
+        if self.get(keyword).is_none() {
+            self.0.push(KeywordDef {
+                name: keyword.to_string(),
+                aliases: vec![],
+                severity: None,
+                color: None,
+                description: None,
+            })
+        }
+    }
+
+    /// the regex patterns of keywords in the mode. The keyword having definition
+    /// is all names and aliases of its definition
+    fn patterns(&self, keywords: &[String], mode: KeywordMode) -> Result<Vec<String>, String> {
+        let mut all: Vec<String> = vec![];
+        for k in keywords {
+            let names = match self.get(k) {
+                Some(d) => std::iter::once(&d.name)
+                    .chain(&d.aliases)
+                    .map(|n| regex::escape(n))
+                    .collect(),
+                None => {
+                    let p = mode.pattern(k);
+                    Regex::new(&p).map_err(|e| format!("invalid keyword {}: {}", k, e))?;
+                    vec![p]
+                }
+            };
+            for n in names {
+                if !all.contains(&n) {
+                    all.push(n)
+                }
+            }
+        }
+        Ok(all)
+    }
+}
+
+impl FromIterator<KeywordDef> for KeywordRegistry {
+    fn from_iter<I: IntoIterator<Item = KeywordDef>>(iter: I) -> Self {
+        let mut registry = Self::default();
+        iter.into_iter().for_each(|d| registry.define(d));
+        registry
+    }
+}
+
+/// the columns of csv/tsv output
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Column {
+    Path,
+    /// the cell of notebook, empty for other files
+    Cell,
+    Line,
+    Column,
+    Keyword,
+    Text,
+    Ignored,
+    Tails,
+    Author,
+    Email,
+    Commit,
+    Date,
+}
+
+impl Column {
+    pub const ALL: [Column; 12] = [
+        Column::Path,
+        Column::Cell,
+        Column::Line,
+        Column::Column,
+        Column::Keyword,
+        Column::Text,
+        Column::Ignored,
+        Column::Tails,
+        Column::Author,
+        Column::Email,
+        Column::Commit,
+        Column::Date,
+    ];
+
+    /// the columns without blame
+    pub const DEFAULT: [Column; 8] = [
+        Column::Path,
+        Column::Cell,
+        Column::Line,
+        Column::Column,
+        Column::Keyword,
+        Column::Text,
+        Column::Ignored,
+        Column::Tails,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Column::Path => "path",
+            Column::Cell => "cell",
+            Column::Line => "line",
+            Column::Column => "column",
+            Column::Keyword => "keyword",
+            Column::Text => "text",
+            Column::Ignored => "ignored",
+            Column::Tails => "tails",
+            Column::Author => "author",
+            Column::Email => "email",
+            Column::Commit => "commit",
+            Column::Date => "date",
+        }
+    }
+}
+
+impl FromStr for Column {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Column::ALL
+            .into_iter()
+            .find(|c| c.as_str() == s.trim().to_lowercase())
+            .ok_or(format!(
+                "unknown column {}, should be one of {}",
+                s,
+                Column::ALL.map(|c| c.as_str()).join(",")
+            ))
+    }
+}
+
+/// parse the comma separated columns
+fn parse_columns(s: &str) -> Result<Vec<Column>, String> {
+    s.split(',').map(|c| c.parse()).collect()
+}
+
+/// how to sort the crumbs
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SortBy {
+    /// the oldest crumb first, by git blame
+    Age,
+    /// the most severe crumb first
+    Severity,
+}
+
+impl FromStr for SortBy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "age" => Ok(SortBy::Age),
+            "severity" => Ok(SortBy::Severity),
+            _ => Err(format!("unknown sort {}, should be age or severity", s)),
+        }
+    }
+}
+
+/// the crumbs selected to delete/restore. All given conditions have to match,
+/// and one of the values is enough for the condition having several values
+#[derive(Clone, Debug, Default)]
+pub struct Selection {
+    files: Vec<String>,
+    /// the first line of crumb in this range (inclusive)
+    lines: Option<(usize, usize)>,
+    ids: Vec<String>,
+    regex: Option<Regex>,
+    ignored: Option<bool>,
+    /// only the plain comments (adopt) or only the crumbs (restore)
+    plain: Option<bool>,
+}
+
+impl Selection {
+    fn new(a: &Args) -> Result<Self, String> {
+        Ok(Self {
+            files: a
+                .select_files
+                .iter()
+                .map(|f| markdown_link_path(f).trim_end_matches('/').to_string())
+                .collect(),
+            lines: a.select_lines.as_deref().map(parse_lines).transpose()?,
+            ids: a
+                .select_ids
+                .iter()
+                .map(|id| markdown_link_path(id).to_string())
+                .collect(),
+            regex: a
+                .select_regex
+                .as_deref()
+                .map(Regex::new)
+                .transpose()
+                .map_err(|e| e.to_string())?,
+            ignored: a.select_ignored,
+            plain: if matches!(a.command, Some(Command::Adopt { .. })) {
+                Some(true)
+            } else if a.restore && !a.delete {
+                Some(false)
+            } else {
+                None
+            },
+        })
+    }
+
+    fn is_selected(&self, bread: &Bread, crumb: &Crumb) -> bool {
+        let path = markdown_link_path(&bread.file_path);
+        (self.files.is_empty()
+            || self
+                .files
+                .iter()
+                .any(|f| path == f || path.starts_with(&format!("{}/", f))))
+            && self
+                .lines
+                .is_none_or(|(start, end)| (start..=end).contains(&crumb.line_num))
+            && (self.ids.is_empty() || self.ids.contains(&bread.crumb_id(crumb)))
+            && self
+                .regex
+                .as_ref()
+                .is_none_or(|re| re.is_match(&crumb.content) || re.is_match(&crumb.view_content))
+            && self.ignored.is_none_or(|i| i == crumb.is_ignore())
+            && self.plain.is_none_or(|p| p == crumb.is_plain())
+    }
+
+    /// keep the selected crumbs of bread, None if nothing left
+    pub fn select(&self, mut bread: Bread) -> Option<Bread> {
+        let crumbs = std::mem::take(&mut bread.crumbs)
+            .into_iter()
+            .filter(|c| self.is_selected(&bread, c))
+            .collect::<Vec<_>>();
+        if crumbs.is_empty() {
+            return None;
+        }
+        bread.crumbs = crumbs;
+        Some(bread)
+    }
+}
+
+/// parse the line range `10-20`, `10-`, `-20` or `10`
+fn parse_lines(s: &str) -> Result<(usize, usize), String> {
+    let err = || format!("invalid line range {}, should be like 10-20, 10- or 10", s);
+    let num = |n: &str, default: usize| {
+        if n.trim().is_empty() {
+            Ok(default)
+        } else {
+            n.trim().parse::<usize>().map_err(|_| err())
+        }
+    };
+    match s.split_once('-') {
+        Some((start, end)) => Ok((num(start, 1)?, num(end, usize::MAX)?)),
+        None => {
+            let line = s.trim().parse().map_err(|_| err())?;
+            Ok((line, line))
+        }
+    }
+}
+
+/// how to group the crumbs in the document output
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum GroupBy {
+    #[default]
+    File,
+    Keyword,
+}
+
+impl FromStr for GroupBy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "file" => Ok(GroupBy::File),
+            "keyword" => Ok(GroupBy::Keyword),
+            _ => Err(format!("unknown group by {}, should be file/keyword", s)),
+        }
+    }
+}
+
+/// how the crumb lines next to each other are grouped to one crumb. The tail
+/// line has to have the same comment header and column as the first line, and
+/// the line starting with the new keyword is always the new crumb
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum GroupMode {
+    /// the line after the line ending with `...`
+    #[default]
+    Explicit,
+    /// all consecutive lines
+    Consecutive,
+    /// the line whose content is indented more than the first line, or after `...`
+    Indent,
+}
+
+impl FromStr for GroupMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "explicit" => Ok(GroupMode::Explicit),
+            "consecutive" => Ok(GroupMode::Consecutive),
+            "indent" => Ok(GroupMode::Indent),
+            _ => Err(format!(
+                "unknown group mode {}, should be explicit/consecutive/indent",
+                s
+            )),
+        }
+    }
+}
+
 /// config when running
 #[derive(Default, Debug, Clone)]
 pub struct Config {
@@ -128,6 +851,76 @@ pub struct Config {
 
     /// show the range of the content around the crumb
     pub(super) range: u32,
+
+    /// group the crumbs by file or keyword in document output
+    pub(super) group_by: GroupBy,
+
+    /// how the crumb lines are grouped to one crumb
+    pub(super) group_mode: GroupMode,
+
+    /// the severity of keywords
+    pub(super) severities: SeverityTable,
+
+    /// the keyword definitions normalizing the crumb keywords
+    pub(super) keywords: KeywordRegistry,
+
+    /// hide the crumbs whose keyword matches
+    pub(super) exclude_keywords: Option<Regex>,
+
+    /// the defined keywords when `-k` isn't given. The crumbs starting with
+    /// them have the keyword, the others are kept without keyword
+    pub(super) defined_keywords: Option<Regex>,
+
+    /// only the crumbs without keyword
+    pub(super) no_keyword: bool,
+
+    /// only the crumbs at or above this severity
+    pub(super) min_severity: Option<Severity>,
+
+    /// fail if any crumb is at or above this severity
+    pub(super) fail_on: Option<Severity>,
+
+    /// the columns of csv/tsv output
+    pub(super) columns: Vec<Column>,
+
+    /// print the summary statistics instead of crumbs
+    pub(super) stats: bool,
+
+    /// add git blame to crumbs
+    pub(super) blame: bool,
+
+    /// only the crumbs older than these seconds
+    pub(super) older_than: Option<i64>,
+
+    /// only the crumbs of these authors (name or email)
+    pub(super) authors: Vec<String>,
+
+    /// sort the crumbs
+    pub(super) sort: Option<SortBy>,
+
+    /// keep watching the targets and re-scan the changed files
+    pub(super) watch: bool,
+
+    /// triage the crumbs in terminal UI
+    pub(super) tui: bool,
+
+    /// delete/restore without prompt
+    pub(super) yes: bool,
+
+    /// never prompt, only print what would change if not `yes`
+    pub(super) no_input: bool,
+
+    /// the crumbs selected to delete/restore
+    pub(super) selection: Selection,
+
+    /// the cache file location if using cache
+    pub(super) cache: Option<String>,
+
+    /// also scan the plain keyword comments without `:=`
+    pub(super) compat: bool,
+
+    /// the subcommand running instead of scanning once
+    pub(super) command: Option<Command>,
 }
 
 impl From<&Args> for Config {
@@ -141,14 +934,139 @@ impl From<&Args> for Config {
             None => (),
         }
 
-        match &a.keywords {
-            Some(kk) => make_key_regex(&kk),
-            None => (),
+        let mut keywords = a
+            .keyword_defs
+            .iter()
+            .map(|d| d.parse())
+            .collect::<Result<KeywordRegistry, _>>()
+            .unwrap_or_else(|e| panic!("{}", e));
+        let keyword_mode: KeywordMode = a
+            .keyword_mode
+            .as_ref()
+            .map(|m| m.parse())
+            .transpose()
+            .unwrap_or_else(|e| panic!("{}", e))
+            .unwrap_or_default();
+        // the keywords given match the aliases of their definitions too
+        let keywords_given = a
+            .keywords
+            .as_ref()
+            .map(|kk| keywords.patterns(kk, keyword_mode))
+            .transpose()
+            .unwrap_or_else(|e| panic!("{}", e));
+        let exclude_keywords = Some(&a.exclude_keywords)
+            .filter(|kk| !kk.is_empty())
+            .map(|kk| {
+                let flag = if a.case_sensitive { "" } else { "(?i)" };
+                let pp = keywords.patterns(kk, keyword_mode)?;
+                Regex::new(&format!("{}^(?:{})$", flag, pp.join("|"))).map_err(|e| e.to_string())
+            })
+            .transpose()
+            .unwrap_or_else(|e| panic!("{}", e));
+        // without `-k`, the crumbs of keyword definitions have their keywords
+        // for the severities, but the other crumbs aren't filtered out
+        let defined_keywords = match &keywords_given {
+            None if !keywords.0.is_empty() => {
+                let flag = if a.case_sensitive { "" } else { "(?i)" };
+                let names = keywords
+                    .0
+                    .iter()
+                    .map(|d| d.name.clone())
+                    .collect::<Vec<_>>();
+                let pp = keywords
+                    .patterns(&names, KeywordMode::Literal)
+                    .unwrap_or_else(|e| panic!("{}", e));
+                Some(Regex::new(&format!("{}^!?({}):\\s*(.*)", flag, pp.join("|"))).unwrap())
+            }
+            _ => None,
+        };
+        // the literal keywords are shown as they are given
+        if keyword_mode == KeywordMode::Literal {
+            a.keywords
+                .iter()
+                .flatten()
+                .for_each(|k| keywords.add_keyword(k));
         }
 
+        if let Some(kk) = &keywords_given {
+            make_key_regex(kk, a.case_sensitive)
+        }
+
+        update_txt_regex(a.txt);
+
+        // adopt needs the plain comments to be scanned
+        let compat = a.compat || matches!(a.command, Some(Command::Adopt { .. }));
+        if compat {
+            update_compat_table(keywords_given.as_ref(), a.case_sensitive);
+        }
+
+        // the severities given directly are prior to the keyword definitions
+        let mut severities = SeverityTable::default();
+        for def in &keywords.0 {
+            if let Some(s) = def.severity {
+                std::iter::once(&def.name)
+                    .chain(&def.aliases)
+                    .for_each(|k| severities.insert(k, s));
+            }
+        }
+        for pair in &a.severities {
+            severities
+                .insert_pair(pair)
+                .unwrap_or_else(|e| panic!("{}", e));
+        }
+
+        let template = match (&a.template, &a.template_file) {
+            (Some(t), _) => Some(Template::inline(t)),
+            (None, Some(f)) => Some(
+                std::fs::read_to_string(f)
+                    .map_err(|e| format!("cannot read template file {}: {}", f, e))
+                    .and_then(|content| Template::from_file_content(&content)),
+            ),
+            (None, None) => None,
+        }
+        .transpose()
+        .unwrap_or_else(|e| panic!("{}", e));
+
+        let older_than = a
+            .older_than
+            .as_ref()
+            .map(|d| blame::parse_duration(d))
+            .transpose()
+            .unwrap_or_else(|e| panic!("{}", e));
+        let sort = a
+            .sort
+            .as_ref()
+            .map(|s| s.parse())
+            .transpose()
+            .unwrap_or_else(|e| panic!("{}", e));
+        let min_severity = a
+            .min_severity
+            .as_ref()
+            .map(|s| s.parse())
+            .transpose()
+            .unwrap_or_else(|e| panic!("{}", e));
+        let fail_on = a
+            .fail_on
+            .as_ref()
+            .map(|s| s.parse())
+            .transpose()
+            .unwrap_or_else(|e| panic!("{}", e));
+
         let output = match &a.output_format {
+            _ if template.is_some() => OutputFormat::Template(template.unwrap()),
             Some(v) if v.to_lowercase().as_str() == "json" => OutputFormat::Json,
+            Some(v) if v.to_lowercase().as_str() == "jsonl" => OutputFormat::Jsonl,
             Some(v) if v.to_lowercase().as_str() == "list" => OutputFormat::List,
+            Some(v) if v.to_lowercase().as_str() == "org" => OutputFormat::Org,
+            Some(v) if matches!(v.to_lowercase().as_str(), "markdown" | "md") => {
+                OutputFormat::Markdown
+            }
+            Some(v) if v.to_lowercase().as_str() == "sarif" => OutputFormat::Sarif,
+            Some(v) if v.to_lowercase().as_str() == "html" => OutputFormat::Html,
+            Some(v) if v.to_lowercase().as_str() == "csv" => OutputFormat::Csv,
+            Some(v) if v.to_lowercase().as_str() == "tsv" => OutputFormat::Tsv,
+            Some(v) if v.to_lowercase().as_str() == "vimgrep" => OutputFormat::Vimgrep,
+            Some(v) if v.to_lowercase().as_str() == "errorformat" => OutputFormat::Errorformat,
             None if a.range > 0 => OutputFormat::Range,
             _ => OutputFormat::None,
         };
@@ -156,7 +1074,10 @@ impl From<&Args> for Config {
         Self {
             filetypes: a.filetypes.clone(),
             ignore_dirs: a.ignore_dirs.clone(),
-            files: a.targets.clone(),
+            files: match &a.command {
+                Some(Command::Adopt { targets }) if !targets.is_empty() => targets.clone(),
+                _ => a.targets.clone(),
+            },
 
             delete: a.delete,
             // delete and restore cannot be true at the same time
@@ -164,9 +1085,66 @@ impl From<&Args> for Config {
             restore: if a.delete { false } else { a.restore },
 
             output,
-            show_ignored: a.show_ignore,
+            // selecting the ignored crumbs needs them to be scanned
+            show_ignored: a.show_ignore || a.select_ignored == Some(true),
 
             range: a.range,
+
+            group_by: a
+                .group_by
+                .as_ref()
+                .map(|g| g.parse())
+                .transpose()
+                .unwrap_or_else(|e| panic!("{}", e))
+                .unwrap_or_default(),
+
+            group_mode: a
+                .group_mode
+                .as_ref()
+                .map(|m| m.parse())
+                .transpose()
+                .unwrap_or_else(|e| panic!("{}", e))
+                .unwrap_or_default(),
+
+            severities,
+            keywords,
+            exclude_keywords,
+            defined_keywords,
+            no_keyword: a.no_keyword,
+            min_severity,
+            fail_on,
+
+            columns: match &a.columns {
+                Some(c) => parse_columns(c).unwrap_or_else(|e| panic!("{}", e)),
+                // blame columns are default when blame is on
+                None if a.blame => Column::ALL.to_vec(),
+                None => Column::DEFAULT.to_vec(),
+            },
+
+            stats: a.stats,
+
+            // filters and sorting by age need the blame
+            blame: a.blame
+                || older_than.is_some()
+                || !a.authors.is_empty()
+                || sort == Some(SortBy::Age),
+            older_than,
+            authors: a.authors.clone(),
+            sort,
+
+            watch: a.watch,
+
+            tui: a.tui,
+
+            yes: a.yes,
+            no_input: a.no_input,
+            selection: Selection::new(a).unwrap_or_else(|e| panic!("{}", e)),
+
+            cache: a.cache.clone(),
+
+            compat,
+
+            command: a.command.clone(),
         }
     }
 }
@@ -248,6 +1226,47 @@ mod tests {
             make_regex(&vec![String::from("//"), String::from(r#"/\*"#)]),
             String::from(r#"(//+|/\*+):=\s+(.*)"#)
         );
+
+        // the closer of block comment isn't the part of content
+        let re = Regex::new(&make_regex(&vec![String::from("<!--")])).unwrap();
+        assert_eq!(
+            &re.captures("<!--:= TODO: a -->  <p>").unwrap()[2],
+            "TODO: a"
+        );
+        assert_eq!(
+            &re.captures("<!--:= TODO: a...\n").unwrap()[2],
+            "TODO: a..."
+        );
+    }
+
+    #[test]
+    fn test_comment_symbol() {
+        assert_eq!(comment_symbol("rs"), Some("//".to_string()));
+        assert_eq!(comment_symbol("py"), Some("#".to_string()));
+        assert_eq!(comment_symbol("hs"), Some("-- ".to_string()));
+        assert_eq!(comment_symbol("unknown"), None);
+    }
+
+    #[test]
+    fn test_make_compat_regex() {
+        let re = Regex::new(&make_compat_regex(&vec![String::from("//")], None, false)).unwrap();
+        let cap = re.captures("    let a = 1; // FIXME handle it").unwrap();
+        assert_eq!((&cap[1], &cap[2], &cap[3]), ("//", "FIXME", "handle it"));
+        assert_eq!(cap.get(0).unwrap().start(), 15);
+        assert!(re.is_match("/// TODO: doc"));
+        assert!(!re.is_match("// todo: lower case"));
+        assert!(!re.is_match("// TODOS are here"));
+        assert!(!re.is_match("// Note: not keyword"));
+
+        let re = Regex::new(&make_compat_regex(
+            &vec![String::from("//")],
+            Some(&vec![String::from("todo"), String::from("note")]),
+            false,
+        ))
+        .unwrap();
+        assert!(re.is_match("// TODO: upper case"));
+        assert!(re.is_match("// Note: keyword"));
+        assert!(!re.is_match("// FIXME: not keyword"));
     }
 
     #[test]
@@ -281,6 +1300,166 @@ mod tests {
         assert_eq!(&re.captures("err // := test").unwrap()[1], "// ");
     }
 
+    #[test]
+    fn test_severity_table() {
+        let mut table = SeverityTable::default();
+        assert_eq!(table.get(Some("fixme")), Severity::Error);
+        assert_eq!(table.get(Some("TODO")), Severity::Warning);
+        assert_eq!(table.get(Some("JIRA-1")), Severity::Info);
+        assert_eq!(table.get(None), Severity::Info);
+
+        table.insert_pair("todo=error").unwrap();
+        table.insert_pair("jira-1=warn").unwrap();
+        assert_eq!(table.get(Some("TODO")), Severity::Error);
+        assert_eq!(table.get(Some("JIRA-1")), Severity::Warning);
+
+        assert!(table.insert_pair("TODO").is_err());
+        assert!(table.insert_pair("TODO=fatal").is_err());
+    }
+
+    #[test]
+    fn test_keyword_mode() {
+        assert_eq!(KeywordMode::Literal.pattern("C++"), r"C\+\+");
+        assert_eq!(KeywordMode::Regex.pattern("JIRA|BUG"), "(?:JIRA|BUG)");
+        assert_eq!(
+            KeywordMode::Glob.pattern("JIRA-*.?"),
+            r"JIRA\-[^:\s]*\.[^:\s]"
+        );
+        assert!("Glob".parse::<KeywordMode>().is_ok());
+        assert!("fuzzy".parse::<KeywordMode>().is_err());
+
+        let registry = KeywordRegistry::default();
+        assert!(
+            registry
+                .patterns(&["JIRA-(".to_string()], KeywordMode::Regex)
+                .is_err()
+        );
+        let re = Regex::new(&format!(
+            "^({})$",
+            registry
+                .patterns(&["JIRA-*".to_string()], KeywordMode::Glob)
+                .unwrap()[0]
+        ))
+        .unwrap();
+        assert!(re.is_match("JIRA-123"));
+        assert!(!re.is_match("JIRA-1 2"));
+    }
+
+    #[test]
+    fn test_group_by() {
+        assert_eq!("Keyword".parse::<GroupBy>(), Ok(GroupBy::Keyword));
+        assert_eq!("file".parse::<GroupBy>(), Ok(GroupBy::File));
+        assert!("author".parse::<GroupBy>().is_err());
+    }
+
+    #[test]
+    fn test_keyword_registry() {
+        let def: KeywordDef = "FIXME, fix,bug=error:red:must fix: before release"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            def,
+            KeywordDef {
+                name: "FIXME".to_string(),
+                aliases: vec!["fix".to_string(), "bug".to_string()],
+                severity: Some(Severity::Error),
+                color: Some(Color::Red),
+                description: Some("must fix: before release".to_string()),
+            }
+        );
+        let hack: KeywordDef = "HACK=:yellow".parse().unwrap();
+        assert_eq!((hack.severity, hack.color), (None, Some(Color::Yellow)));
+        assert!("FIXME".parse::<KeywordDef>().is_err());
+        assert!("=error".parse::<KeywordDef>().is_err());
+        assert!("FIXME=fatal".parse::<KeywordDef>().is_err());
+        assert!("FIXME=error:pink".parse::<KeywordDef>().is_err());
+
+        let mut registry = [def, hack].into_iter().collect::<KeywordRegistry>();
+        registry.add_keyword("todo");
+        registry.add_keyword("Bug");
+        assert_eq!(registry.canonical("Fix"), "FIXME");
+        assert_eq!(registry.canonical("TODO"), "todo");
+        assert_eq!(registry.canonical("MARK"), "MARK");
+        assert_eq!(
+            registry
+                .patterns(
+                    &["bug".to_string(), "TODO".to_string()],
+                    KeywordMode::Literal
+                )
+                .unwrap(),
+            vec!["FIXME", "fix", "bug", "todo"]
+        );
+        assert_eq!(registry.paint("fix"), "\x1b[31mfix\x1b[0m");
+        assert_eq!(registry.paint("todo"), "todo");
+        assert_eq!(
+            registry.description("bug"),
+            Some("must fix: before release")
+        );
+
+        // the later definition replaces the one with same name
+        registry.define("fixme=warning".parse().unwrap());
+        assert_eq!(registry.canonical("fixme"), "fixme");
+        assert_eq!(registry.get("bug"), None);
+    }
+
+    #[test]
+    fn test_parse_columns() {
+        assert_eq!(
+            parse_columns("line,Path, text").unwrap(),
+            vec![Column::Line, Column::Path, Column::Text]
+        );
+        assert!(parse_columns("line,owner").is_err());
+    }
+
+    #[test]
+    fn test_selection() {
+        assert_eq!(parse_lines("10-20").unwrap(), (10, 20));
+        assert_eq!(parse_lines("10-").unwrap(), (10, usize::MAX));
+        assert_eq!(parse_lines("-20").unwrap(), (1, 20));
+        assert_eq!(parse_lines("7").unwrap(), (7, 7));
+        assert!(parse_lines("a-b").is_err());
+
+        let crumb =
+            |line, content: &str| Crumb::new(line, 0, content.to_string(), "//".to_string());
+        let bread = Bread::new(
+            "./src/a.rs".to_string(),
+            vec![
+                crumb(1, "TODO: one"),
+                crumb(5, "FIXME: two"),
+                crumb(9, "!TODO: three").add_ignore_flag(),
+            ],
+        );
+        let lines = |args: &Args| {
+            Selection::new(args)
+                .unwrap()
+                .select(bread.clone())
+                .map(|b| b.crumbs.iter().map(|c| c.line_num).collect::<Vec<_>>())
+                .unwrap_or_default()
+        };
+
+        let mut args = Args::default();
+        assert_eq!(lines(&args), vec![1, 5, 9]);
+
+        args.select_files = vec!["src/".to_string()];
+        args.select_regex = Some("^(TODO|FIXME)".to_string());
+        assert_eq!(lines(&args), vec![1, 5]);
+
+        args.select_lines = Some("2-".to_string());
+        assert_eq!(lines(&args), vec![5]);
+
+        args.select_lines = None;
+        args.select_regex = None;
+        args.select_ignored = Some(true);
+        assert_eq!(lines(&args), vec![9]);
+
+        args.select_ignored = None;
+        args.select_ids = vec!["./src/a.rs:5".to_string(), "src/a.rs:6".to_string()];
+        assert_eq!(lines(&args), vec![5]);
+
+        args.select_files = vec!["src/b.rs".to_string()];
+        assert_eq!(Selection::new(&args).unwrap().select(bread), None);
+    }
+
     #[test]
     fn test_restore_overwrited_by_delete() {
         let mut arg: Args = Default::default();
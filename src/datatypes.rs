@@ -1,9 +1,10 @@
 use regex::Regex;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::fmt;
+use std::path::Path;
 
 /// major data struct including file path and all crumbs
-#[derive(Debug, PartialEq, Eq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Bread {
     pub(super) file_path: String,
     pub(super) crumbs: Vec<Crumb>,
@@ -17,37 +18,89 @@ impl Bread {
         }
     }
 
+    /// the language of this bread, which is the file extension
+    /// as the key of the comment symbols table
+    pub fn language(&self) -> String {
+        Path::new(&self.file_path)
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or_default()
+            .to_string()
+    }
+
+    /// org-mode document of this bread, file path as the top headline
+    /// and every crumb as the second level headline under it
     pub fn to_org(&self) -> Result<String, !> {
         let mut content = format!("* {}\n", self.file_path);
+        self.crumbs.iter().for_each(|c| {
+            content += "** ";
+            content += &c.to_org(&self.file_path);
+        });
+        Ok(content)
+    }
+
+    /// markdown section of this bread, file path as the heading
+    pub fn to_markdown(&self) -> String {
+        let mut content = format!(
+            "## [{}]({})\n\n",
+            self.file_path,
+            markdown_link_path(&self.file_path)
+        );
         self.crumbs
             .iter()
-            .filter_map(|c| c.to_org())
-            .for_each(|org_inside| {
-                content += "** ";
-                content += &org_inside;
-                content += "\n"
-            });
-        Ok(content)
+            .for_each(|c| content.push_str(&c.to_markdown(&self.file_path, false)));
+        content
+    }
+
+    /// the id of the crumb in this bread, `path:line` of the crumb's first line,
+    /// or `path:cell:line` in notebook
+    pub fn crumb_id(&self, crumb: &Crumb) -> String {
+        match crumb.cell {
+            Some(cell) => format!(
+                "{}:{}:{}",
+                markdown_link_path(&self.file_path),
+                cell,
+                crumb.line_num
+            ),
+            None => format!("{}:{}", markdown_link_path(&self.file_path), crumb.line_num),
+        }
     }
 }
 
-impl fmt::Display for Bread {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "|-- {}\n", self.file_path)?; // write file_path
+/// the file path and line number of the crumb id
+pub fn parse_crumb_id(id: &str) -> Option<(&str, usize)> {
+    let (path, line) = id.rsplit_once(':')?;
+    Some((path, line.parse().ok()?))
+}
 
+impl Bread {
+    /// the tree view same as Display, with the keywords painted
+    pub fn to_painted(&self, paint: impl Fn(&str) -> String) -> String {
+        let mut content = format!("|-- {}\n", self.file_path); // write file_path
         for c in &self.crumbs {
-            write!(f, "  |-- {}", c)?;
+            content.push_str("  |-- ");
+            content.push_str(&c.to_painted(&paint));
         }
-        Ok(())
+        content
+    }
+}
+
+impl fmt::Display for Bread {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_painted(|k| k.to_string()))
     }
 }
 
 /// Crumb including the data of this line
-#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Crumb {
     pub(crate) line_num: usize,
 
-    #[serde(skip)]
+    /// the cell of notebook this crumb in, starts from 1.
+    /// The `line_num` is the line in this cell
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) cell: Option<usize>,
+
     /// the position of the crumb start from in this line
     pub(crate) position: usize,
 
@@ -67,13 +120,53 @@ pub struct Crumb {
 
     /// record the crumb header for restore
     /// like in lisp `;;;:= here`, `;;;` should be header
-    comment_symbol_header: String,
+    pub(crate) comment_symbol_header: String,
 
     /// ignore this crumb or not
     ignore: bool,
 
+    /// the plain keyword comment without `:=` found in compat mode,
+    /// it isn't the crumb until adopted
+    #[serde(default)]
+    plain: bool,
+
     /// range content
     pub(crate) range_content: Option<Vec<(usize, String)>>,
+
+    /// git blame of this line when `--blame` is given
+    pub(crate) blame: Option<Blame>,
+}
+
+/// the git blame information of one line
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Blame {
+    pub author: String,
+    pub email: String,
+    pub commit: String,
+    /// the unix timestamp of author time
+    pub timestamp: i64,
+}
+
+impl Blame {
+    /// the author date in `YYYY-MM-DD` (UTC)
+    pub fn date(&self) -> String {
+        let (y, m, d) = civil_from_days(self.timestamp.div_euclid(86400));
+        format!("{:04}-{:02}-{:02}", y, m, d)
+    }
+}
+
+/// days since 1970-01-01 to (year, month, day)
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let y = yoe + era * 400;
+    (if m <= 2 { y + 1 } else { y }, m, d)
 }
 
 impl Crumb {
@@ -89,6 +182,7 @@ impl Crumb {
     ) -> Self {
         Self {
             line_num,
+            cell: None,
             position,
             tails,
             keyword,
@@ -96,24 +190,45 @@ impl Crumb {
             content,
             comment_symbol_header,
             ignore,
+            plain: false,
             range_content: None,
+            blame: None,
         }
     }
 
-    /// side effect: will change keyword to Some(_) if match successed
+    /// side effect: will change keyword to Some(_) if match successed.
+    /// The content is the last group, the keyword regex may have groups inside
     pub fn filter_keywords(&mut self, re: &Regex) -> bool {
         match re.captures(&self.content) {
             Some(a) => {
                 self.keyword = Some(a[1].to_string());
-                self.view_content = a[2].to_string();
+                self.view_content = a[a.len() - 1].to_string();
                 true
             }
             None => false,
         }
     }
 
+    /// the content starts with the keyword like `TODO: ` (or `!TODO: `)
+    pub fn has_keyword_prefix(&self) -> bool {
+        self.content
+            .trim_start_matches('!')
+            .split_once(':')
+            .is_some_and(|(k, rest)| {
+                !k.is_empty()
+                    && !k.contains(char::is_whitespace)
+                    && (rest.is_empty() || rest.starts_with(char::is_whitespace))
+            })
+    }
+
+    /// the crumb has the same comment header and position as other
+    pub fn is_aligned_with(&self, other: &Crumb) -> bool {
+        self.comment_symbol_header == other.comment_symbol_header && self.position == other.position
+    }
+
     pub fn has_tail(&self) -> bool {
-        self.view_content.ends_with("...")
+        // the plain comment is always one line
+        !self.plain && self.view_content.ends_with("...")
     }
 
     /// add tail crumbs in this one
@@ -137,6 +252,7 @@ impl Crumb {
     ) -> Self {
         Self {
             line_num,
+            cell: None,
             position,
             keyword: None,
             tails: vec![],
@@ -144,16 +260,130 @@ impl Crumb {
             content,
             comment_symbol_header,
             ignore: false,
+            plain: false,
             range_content: None,
+            blame: None,
         }
     }
 
-    /// keyword crumb can transfer to org string
-    pub fn to_org(&self) -> Option<String> {
-        match &self.keyword {
-            Some(k) => Some(format!("{} {}", k, self.content)),
-            None => None,
+    /// org headline (without the stars) of this crumb, including the
+    /// properties drawer and the link back to the source line.
+    /// keyword crumb get the TODO/DONE state and the keyword tag
+    pub fn to_org(&self, file_path: &str) -> String {
+        let mut tags = vec![];
+        let mut headline = match &self.keyword {
+            Some(k) => {
+                tags.push(org_tag(k));
+                format!("{} {}", org_todo_state(k), self.view_content)
+            }
+            None => self.view_content.clone(),
+        };
+
+        if self.ignore {
+            tags.push("ignored".to_string());
         }
+
+        if !tags.is_empty() {
+            headline.push_str(&format!(" :{}:", tags.join(":")));
+        }
+
+        let mut properties = vec![("FILE", file_path.to_string())];
+        if let Some(cell) = self.cell {
+            properties.push(("CELL", cell.to_string()));
+        }
+        properties.push(("LINE", self.line_num.to_string()));
+        properties.push(("COLUMN", (self.position + 1).to_string()));
+        if let Some(k) = &self.keyword {
+            properties.push(("KEYWORD", k.to_string()));
+        }
+        if let Some(b) = &self.blame {
+            properties.push(("AUTHOR", b.author.clone()));
+            properties.push(("EMAIL", b.email.clone()));
+            properties.push(("COMMIT", b.commit.clone()));
+            properties.push(("DATE", b.date()));
+        }
+        if !self.tails.is_empty() {
+            properties.push((
+                "TAIL_LINES",
+                self.tails
+                    .iter()
+                    .map(|t| t.line_num.to_string())
+                    .collect::<Vec<_>>()
+                    .join(" "),
+            ));
+        }
+
+        let mut content = headline;
+        content.push_str("\n:PROPERTIES:\n");
+        for (k, v) in properties {
+            content.push_str(&format!(":{}: {}\n", k, v));
+        }
+        content.push_str(":END:\n");
+        content.push_str(&match self.cell {
+            // the line of cell isn't the line of notebook file
+            Some(_) => format!(
+                "[[file:{}][{}:{}]]\n",
+                file_path,
+                file_path,
+                self.location()
+            ),
+            None => format!(
+                "[[file:{}::{}][{}:{}]]\n",
+                file_path, self.line_num, file_path, self.line_num
+            ),
+        });
+        content
+    }
+
+    /// markdown task list item of this crumb. `with_path` makes the link
+    /// text include the file path, for the documents not grouped by file
+    pub fn to_markdown(&self, file_path: &str, with_path: bool) -> String {
+        let link = markdown_link_path(file_path);
+        let checked = match &self.keyword {
+            Some(k) if is_done_keyword(k) => "x",
+            _ => " ",
+        };
+        let (target, link_text) = match self.cell {
+            // the notebook has no line anchors
+            Some(cell) => (link.to_string(), format!("cell{}:L{}", cell, self.line_num)),
+            None => (
+                format!("{}#L{}", link, self.line_num),
+                format!("L{}", self.line_num),
+            ),
+        };
+        let link_text = match self.cell {
+            Some(_) if with_path => format!("{}:{}", link, link_text),
+            None if with_path => target.clone(),
+            _ => link_text,
+        };
+
+        let mut content = format!("- [{}] [{}]({}) ", checked, link_text, target);
+        if let Some(k) = &self.keyword {
+            content.push_str(&format!("**{}**: ", k));
+        }
+        content.push_str(&self.view_content);
+        if self.ignore {
+            content.push_str(" _(ignored)_");
+        }
+        if let Some(b) = &self.blame {
+            content.push_str(&format!(" — {}, {}", b.author, b.date()));
+        }
+        content.push('\n');
+
+        if let Some(range) = &self.range_content {
+            let lang = Path::new(file_path)
+                .extension()
+                .and_then(|e| e.to_str())
+                .unwrap_or("");
+            content.push_str(&format!("  ```{}\n", lang));
+            for (_, line) in range {
+                content.push_str("  ");
+                content.push_str(line.trim_end_matches(['\n', '\r']));
+                content.push('\n');
+            }
+            content.push_str("  ```\n");
+        }
+        content
     }
 
     /// return this crumb line_num and all tails line numbers if it has tails
@@ -201,6 +431,27 @@ impl Crumb {
         a
     }
 
+    /// set the blame of this crumb and all tails by their line numbers
+    pub fn set_blames(&mut self, blame_of_line: impl Fn(usize) -> Option<Blame>) {
+        self.blame = blame_of_line(self.line_num);
+        self.tails
+            .iter_mut()
+            .for_each(|t| t.blame = blame_of_line(t.line_num));
+    }
+
+    /// the blames of all tails
+    pub fn tails_blames(&self) -> Vec<Option<Blame>> {
+        self.tails.iter().map(|t| t.blame.clone()).collect()
+    }
+
+    /// ` (author, date)` if this crumb has blame
+    pub fn blame_suffix(&self) -> String {
+        match &self.blame {
+            Some(b) => format!(" ({}, {})", b.author, b.date()),
+            None => String::new(),
+        }
+    }
+
     // add the ignore flag to this crumb
     pub fn add_ignore_flag(mut self) -> Self {
         self.ignore = true;
@@ -211,6 +462,37 @@ impl Crumb {
         self.ignore
     }
 
+    // add the plain flag to this crumb
+    pub fn add_plain_flag(mut self) -> Self {
+        self.plain = true;
+        self
+    }
+
+    pub fn is_plain(&self) -> bool {
+        self.plain
+    }
+
+    // set the cell of notebook this crumb in
+    pub fn in_cell(mut self, cell: usize) -> Self {
+        self.cell = Some(cell);
+        self
+    }
+
+    /// the keyword of crumb, or the word before `:` if keywords aren't given
+    pub fn keyword_name(&self) -> Option<&str> {
+        self.keyword
+            .as_deref()
+            .or_else(|| self.content.split_once(':').map(|(k, _)| k.trim()))
+    }
+
+    /// the line number, or `cellN:line` if the crumb is in the notebook cell
+    pub fn location(&self) -> String {
+        match self.cell {
+            Some(cell) => format!("cell{}:{}", cell, self.line_num),
+            None => self.line_num.to_string(),
+        }
+    }
+
     pub fn list_format(&self) -> String {
         let kw = match self.keyword {
             Some(ref k) => {
@@ -220,7 +502,44 @@ impl Crumb {
             }
             None => "".to_string(),
         };
-        format!("{}: {}{}", self.line_num, kw, self.view_content)
+        format!(
+            "{}: {}{}{}",
+            self.location(),
+            kw,
+            self.view_content,
+            self.blame_suffix()
+        )
+    }
+
+    /// `line:column: keyword: content` format for vim's `:cgetexpr`/grep,
+    /// the line is `cellN:line` in notebook
+    pub fn vimgrep_format(&self) -> String {
+        format!(
+            "{}:{}: {}",
+            self.location(),
+            self.position + 1,
+            self.keyword_and_view()
+        )
+    }
+
+    /// `line:column: severity: keyword: content` format, which is
+    /// the gnu errorformat both vim quickfix and emacs compilation-mode understand.
+    /// The line is `cellN:line` in notebook
+    pub fn errorformat_format(&self, severity: &str) -> String {
+        format!(
+            "{}:{}: {}: {}",
+            self.location(),
+            self.position + 1,
+            severity,
+            self.keyword_and_view()
+        )
+    }
+
+    fn keyword_and_view(&self) -> String {
+        match self.keyword {
+            Some(ref k) => format!("{}: {}{}", k, self.view_content, self.blame_suffix()),
+            None => format!("{}{}", self.view_content, self.blame_suffix()),
+        }
     }
 
     pub fn range_format(&self) -> String {
@@ -235,19 +554,70 @@ impl Crumb {
     }
 }
 
+/// keywords those mean the crumb is already finished
+const DONE_KEYWORDS: [&str; 3] = ["DONE", "FIXED", "CLOSED"];
+
+fn is_done_keyword(keyword: &str) -> bool {
+    DONE_KEYWORDS.iter().any(|k| k.eq_ignore_ascii_case(keyword))
+}
+
+/// map the keyword to the org TODO state
+fn org_todo_state(keyword: &str) -> &'static str {
+    if is_done_keyword(keyword) {
+        "DONE"
+    } else {
+        "TODO"
+    }
+}
+
+/// the link of file path used in markdown, relative without the leading `./`
+pub(crate) fn markdown_link_path(file_path: &str) -> &str {
+    file_path.trim_start_matches("./")
+}
+
+/// org tags only accept alphanumeric and `_@#%`
+fn org_tag(keyword: &str) -> String {
+    keyword
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || "_@#%".contains(c) {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
 /// default format
-impl fmt::Display for Crumb {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+impl Crumb {
+    /// the line same as Display, with the keyword painted
+    pub fn to_painted(&self, paint: impl Fn(&str) -> String) -> String {
         let a = match self.keyword {
             Some(ref k) => {
-                let mut c = String::from(k);
+                let mut c = paint(k);
                 c.push_str(": ");
                 c
             }
             None => "".to_string(),
         };
-        write!(f, "Line {}: {}{}\n", self.line_num, a, self.view_content)?;
-        Ok(())
+        let location = match self.cell {
+            Some(cell) => format!("Cell {} Line {}", cell, self.line_num),
+            None => format!("Line {}", self.line_num),
+        };
+        format!(
+            "{}: {}{}{}\n",
+            location,
+            a,
+            self.view_content,
+            self.blame_suffix()
+        )
+    }
+}
+
+impl fmt::Display for Crumb {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_painted(|k| k.to_string()))
     }
 }
 
@@ -288,4 +658,169 @@ mod tests {
         //dbg!(&a);
         assert_eq!(a.keyword, Some("TODO".to_string()));
     }
+
+    #[test]
+    fn test_keyword_prefix_and_groups() {
+        let crumb = |content: &str| Crumb::new(1, 0, content.to_string(), "//".to_string());
+        assert!(crumb("TODO: a").has_keyword_prefix());
+        assert!(crumb("!JIRA-1:").has_keyword_prefix());
+        assert!(!crumb("see http://a.b").has_keyword_prefix());
+        assert!(!crumb("plain note").has_keyword_prefix());
+        assert!(!crumb(": a").has_keyword_prefix());
+
+        // the groups inside the keyword regex
+        let mut a = crumb("JIRA-12: d");
+        assert!(a.filter_keywords(&Regex::new("(JIRA-(\\d+)):\\s*(.*)").unwrap()));
+        assert_eq!(a.keyword, Some("JIRA-12".to_string()));
+        assert_eq!(a.view_content, "d");
+    }
+
+    #[test]
+    fn test_to_org() {
+        let mut a = Crumb::new(3, 4, "TODO: fix it".to_string(), "//".to_string());
+        a.filter_keywords(&Regex::new("(TODO|JIRA-1):\\s*(.*)").unwrap());
+        assert_eq!(
+            a.to_org("src/a.rs"),
+            r#"TODO fix it :TODO:
+:PROPERTIES:
+:FILE: src/a.rs
+:LINE: 3
+:COLUMN: 5
+:KEYWORD: TODO
+:END:
+[[file:src/a.rs::3][src/a.rs:3]]
+"#
+        );
+
+        let mut a = Crumb::new(1, 0, "!JIRA-1: done...".to_string(), "//".to_string())
+            .add_ignore_flag();
+        a.filter_keywords(&Regex::new("(TODO|JIRA-1):\\s*(.*)").unwrap());
+        a.add_tail(Crumb::new(2, 0, "here".to_string(), "//".to_string()));
+        assert!(
+            a.to_org("a.rs")
+                .starts_with("TODO done here :JIRA_1:ignored:\n")
+        );
+        assert!(a.to_org("a.rs").contains(":TAIL_LINES: 2\n"));
+
+        let a = Crumb::new(7, 0, "no keyword".to_string(), "//".to_string());
+        let b = Bread::new("a.rs".to_string(), vec![a]);
+        assert_eq!(
+            b.to_org().unwrap(),
+            "* a.rs\n** no keyword\n:PROPERTIES:\n:FILE: a.rs\n:LINE: 7\n:COLUMN: 1\n:END:\n[[file:a.rs::7][a.rs:7]]\n"
+        );
+
+        assert_eq!(org_todo_state("fixed"), "DONE");
+        assert_eq!(org_todo_state("FIXME"), "TODO");
+    }
+
+    #[test]
+    fn test_crumb_id() {
+        let b = Bread::new(
+            "./src/a:b.rs".to_string(),
+            vec![Crumb::new(3, 0, "a".to_string(), "//".to_string())],
+        );
+        assert_eq!(b.crumb_id(&b.crumbs[0]), "src/a:b.rs:3");
+        assert_eq!(parse_crumb_id("src/a:b.rs:3"), Some(("src/a:b.rs", 3)));
+        assert_eq!(parse_crumb_id("src/a.rs"), None);
+
+        let b = Bread::new(
+            "./a.ipynb".to_string(),
+            vec![Crumb::new(3, 0, "a".to_string(), "#".to_string()).in_cell(2)],
+        );
+        assert_eq!(b.crumb_id(&b.crumbs[0]), "a.ipynb:2:3");
+        assert_eq!(b.crumbs[0].to_string(), "Cell 2 Line 3: a\n");
+    }
+
+    #[test]
+    fn test_blame_date() {
+        let mut b = Blame::default();
+        assert_eq!(b.date(), "1970-01-01");
+        b.timestamp = 1700000000;
+        assert_eq!(b.date(), "2023-11-14");
+        b.timestamp = 951782400; // leap day
+        assert_eq!(b.date(), "2000-02-29");
+        b.timestamp = -86400;
+        assert_eq!(b.date(), "1969-12-31");
+    }
+
+    #[test]
+    fn test_blame_suffix() {
+        let mut a = Crumb::new(3, 4, "fix it...".to_string(), "//".to_string());
+        a.add_tail(Crumb::new(4, 4, "now".to_string(), "//".to_string()));
+        a.set_blames(|ln| {
+            Some(Blame {
+                author: format!("a{}", ln),
+                timestamp: 1700000000,
+                ..Default::default()
+            })
+        });
+
+        assert_eq!(a.list_format(), "3: fix it now (a3, 2023-11-14)");
+        assert_eq!(a.tails_blames()[0].as_ref().unwrap().author, "a4");
+        assert!(a.to_org("a.rs").contains(":AUTHOR: a3\n"));
+    }
+
+    #[test]
+    fn test_vimgrep_and_errorformat() {
+        let mut a = Crumb::new(3, 4, "FIXME: fix it".to_string(), "//".to_string());
+        assert_eq!(a.vimgrep_format(), "3:5: FIXME: fix it");
+        a.filter_keywords(&Regex::new("(FIXME):\\s*(.*)").unwrap());
+        assert_eq!(a.vimgrep_format(), "3:5: FIXME: fix it");
+        assert_eq!(a.errorformat_format("error"), "3:5: error: FIXME: fix it");
+
+        // the line of notebook is in the cell
+        let a = a.in_cell(2);
+        assert_eq!(a.list_format(), "cell2:3: FIXME: fix it");
+        assert_eq!(a.vimgrep_format(), "cell2:3:5: FIXME: fix it");
+        assert_eq!(
+            a.errorformat_format("error"),
+            "cell2:3:5: error: FIXME: fix it"
+        );
+        let org = a.to_org("a.ipynb");
+        assert!(org.contains(":CELL: 2\n:LINE: 3\n:COLUMN: 5\n"));
+        assert!(org.ends_with(":END:\n[[file:a.ipynb][a.ipynb:cell2:3]]\n"));
+    }
+
+    #[test]
+    fn test_to_markdown() {
+        let mut a = Crumb::new(3, 4, "DONE: fix it".to_string(), "//".to_string());
+        a.filter_keywords(&Regex::new("(TODO|DONE):\\s*(.*)").unwrap());
+        assert_eq!(
+            a.to_markdown("./src/a.rs", false),
+            "- [x] [L3](src/a.rs#L3) **DONE**: fix it\n"
+        );
+        assert_eq!(
+            a.to_markdown("./src/a.rs", true),
+            "- [x] [src/a.rs#L3](src/a.rs#L3) **DONE**: fix it\n"
+        );
+
+        // no line anchor in notebook
+        let cell = a.clone().in_cell(2);
+        assert_eq!(
+            cell.to_markdown("./a.ipynb", false),
+            "- [x] [cell2:L3](a.ipynb) **DONE**: fix it\n"
+        );
+        assert_eq!(
+            cell.to_markdown("./a.ipynb", true),
+            "- [x] [a.ipynb:cell2:L3](a.ipynb) **DONE**: fix it\n"
+        );
+
+        let mut a = Crumb::new(2, 0, "!here".to_string(), "//".to_string()).add_ignore_flag();
+        a.range_content = Some(vec![
+            (1, "fn main() {\n".to_string()),
+            (2, "//:= !here\n".to_string()),
+        ]);
+        let b = Bread::new("a.rs".to_string(), vec![a]);
+        assert_eq!(
+            b.to_markdown(),
+            r#"## [a.rs](a.rs)
+
+- [ ] [L2](a.rs#L2) !here _(ignored)_
+  ```rs
+  fn main() {
+  //:= !here
+  ```
+"#
+        );
+    }
 }
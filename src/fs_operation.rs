@@ -1,14 +1,22 @@
-use super::config::{Config, KEYWORDS_REGEX, REGEX_TABLE};
+use super::blame;
+use super::cache;
+use super::config::{
+    COMPAT_REGEX_TABLE, Config, GroupMode, KEYWORDS_REGEX, REGEX_TABLE, SeverityTable, SortBy,
+    comment_closer, comment_symbol,
+};
 use super::datatypes::*;
+use super::notebook::{self, CellKind, Notebook};
 use log::debug;
 use regex::Regex;
+use std::cmp::Reverse;
 use std::collections::{HashMap, HashSet};
 use std::ffi::OsString;
 use std::fs::{self, OpenOptions, read_dir};
 use std::io::{self, BufReader, prelude::*};
 use std::num::NonZeroUsize;
+use std::ops::Range;
 use std::process::Command;
-use std::sync::{Arc, RwLock};
+use std::sync::{Arc, RwLock, mpsc};
 use std::{io::Result, path::Path, path::PathBuf, thread};
 
 /// how many thread when it runs
@@ -178,10 +186,68 @@ fn filter_line(line: &str, line_num: usize, re: &Regex) -> Option<Crumb> {
     }
 }
 
+/// Filter this line for the plain keyword comment in compat mode.
+/// The content is `keyword: rest` even the colon is missing in comment
+fn filter_plain_line(line: &str, line_num: usize, re: &Regex) -> Option<Crumb> {
+    let cap = re.captures(line)?;
+    // the rest is the last group, the keywords may have groups inside
+    let content = format!("{}: {}", &cap[2], &cap[cap.len() - 1])
+        .trim_end()
+        .to_string();
+    Some(Crumb::new(line_num, cap.get(0)?.start(), content, cap[1].to_string()).add_plain_flag())
+}
+
+/// the spaces between `:=` and the content of crumb in the line
+fn content_indent(line: &str, crumb: &Crumb) -> usize {
+    let after = line
+        .get(crumb.position..)
+        .and_then(|l| l.strip_prefix(crumb.comment_symbol_header.as_str()))
+        .and_then(|l| l.strip_prefix(":="))
+        .unwrap_or_default();
+    after.len() - after.trim_start().len()
+}
+
+/// the crumb starts with the keyword, one of the `-k` keywords if they are given
+fn starts_with_keyword(crumb: &Crumb, kwreg: &Option<Regex>) -> bool {
+    match kwreg {
+        Some(re) => re
+            .find(crumb.content.trim_start_matches('!'))
+            .is_some_and(|m| m.start() == 0),
+        None => crumb.has_keyword_prefix(),
+    }
+}
+
+/// the crumb is the tail of head in the group mode. The indents are the
+/// content indents of the crumb and the first line of head
+fn is_tail(
+    head: &Crumb,
+    head_indent: usize,
+    crumb: &Crumb,
+    indent: usize,
+    kwreg: &Option<Regex>,
+    mode: GroupMode,
+) -> bool {
+    // the plain comment is always one line
+    if head.is_plain()
+        || crumb.is_plain()
+        || !crumb.is_aligned_with(head)
+        || starts_with_keyword(crumb, kwreg)
+    {
+        return false;
+    }
+    match mode {
+        GroupMode::Explicit => head.has_tail(),
+        GroupMode::Consecutive => true,
+        GroupMode::Indent => head.has_tail() || indent > head_indent,
+    }
+}
+
 /// Operate this file
 fn op_file(file: File, kwreg: &Option<Regex>, conf: Arc<RwLock<Config>>) -> Result<Option<Bread>> {
     let breads = match bake_bread(&file, kwreg, &conf.read().unwrap()) {
-        Ok(b) => b,
+        Ok(b) => b
+            .and_then(|b| blame_and_filter(b, &conf.read().unwrap()))
+            .and_then(|b| filter_keyword_and_severity(b, &conf.read().unwrap())),
         Err(e) => {
             debug!("file {} had error {}", file.to_string(), e.to_string());
             return Ok(None);
@@ -201,29 +267,306 @@ fn op_file(file: File, kwreg: &Option<Regex>, conf: Arc<RwLock<Config>>) -> Resu
     }
 }
 
+/// bake the bread of this single file, which checked by the filetypes and
+/// ignore dirs like scanning the dirs. None if this file isn't the one should
+/// be scanned, or it doesn't have crumbs
+pub fn bake_file(path: &Path, conf: &Config) -> Option<Bread> {
+    let relative = std::env::current_dir()
+        .ok()
+        .and_then(|cwd| path.strip_prefix(cwd).ok())
+        .unwrap_or(path);
+    if relative
+        .parent()
+        .into_iter()
+        .flat_map(|p| p.iter())
+        .any(|d| conf.ignore_dirs.contains(&d.to_os_string()))
+    {
+        return None;
+    }
+
+    let mut files = vec![];
+    file_checker(&mut files, path, &conf.filetypes, conf.filetypes.len());
+    let file = files.pop()?;
+    let kwreg = KEYWORDS_REGEX.lock().unwrap().clone();
+    match bake_bread(&file, &kwreg, conf) {
+        Ok(b) => b
+            .and_then(|b| blame_and_filter(b, conf))
+            .and_then(|b| filter_keyword_and_severity(b, conf)),
+        Err(e) => {
+            debug!("file {} had error {}", file.to_string(), e);
+            None
+        }
+    }
+}
+
+/// bake the bread from the content of this file rather than reading it, like
+/// the unsaved buffer in editor. None if this file type isn't supported or
+/// the content doesn't have crumbs
+pub fn bake_content(path: &Path, content: &[u8], conf: &Config) -> Option<Bread> {
+    let mut files = vec![];
+    file_checker(&mut files, path, &conf.filetypes, conf.filetypes.len());
+    let file = files.pop()?;
+    let kwreg = KEYWORDS_REGEX.lock().unwrap().clone();
+    bake_bread_from_content(file.to_string(), content, file.1, &kwreg, conf)
+}
+
+/// bake all crumbs of this file as they are written, including the ignored
+/// ones and without the keywords filter. The lines are grouped in group mode
+pub(crate) fn bake_all_crumbs(path: &Path, group_mode: GroupMode) -> Result<Option<Bread>> {
+    let mut files = vec![];
+    file_checker(&mut files, path, &[], 0);
+    let Some(file) = files.pop() else {
+        return Err(io::Error::other(format!(
+            "unsupported file type {}",
+            path.display()
+        )));
+    };
+    let conf = Config {
+        show_ignored: true,
+        group_mode,
+        ..Default::default()
+    };
+    Ok(bake_bread_from_content(
+        file.to_string(),
+        &fs::read(path)?,
+        file.1,
+        &None,
+        &conf,
+    ))
+}
+
+/// add git blame to the bread if it needs, and filter the crumbs by blame
+fn blame_and_filter(mut bread: Bread, conf: &Config) -> Option<Bread> {
+    if !conf.blame {
+        return Some(bread);
+    }
+
+    // the lines of notebook cells aren't the lines of file
+    if !notebook::is_notebook(&bread.file_path) {
+        blame::blame_bread(&mut bread);
+    }
+
+    let now = blame::now();
+    let authors = conf
+        .authors
+        .iter()
+        .map(|a| a.to_lowercase())
+        .collect::<Vec<_>>();
+    bread.crumbs.retain(|c| {
+        let Some(b) = &c.blame else {
+            // no blame cannot pass any filter
+            return conf.older_than.is_none() && authors.is_empty();
+        };
+
+        conf.older_than.is_none_or(|secs| b.timestamp <= now - secs)
+            && (authors.is_empty()
+                || authors.iter().any(|a| {
+                    b.author.to_lowercase().contains(a) || b.email.to_lowercase().contains(a)
+                }))
+    });
+
+    if bread.crumbs.is_empty() {
+        None
+    } else {
+        Some(bread)
+    }
+}
+
+/// only keep the crumbs at or above the min severity, and the crumbs not
+/// having the excluded keywords
+fn filter_keyword_and_severity(mut bread: Bread, conf: &Config) -> Option<Bread> {
+    if conf.min_severity.is_none() && conf.exclude_keywords.is_none() {
+        return Some(bread);
+    }
+    bread.crumbs.retain(|c| {
+        conf.min_severity
+            .is_none_or(|min| conf.severities.get(c.keyword.as_deref()) >= min)
+            && conf.exclude_keywords.as_ref().is_none_or(|re| {
+                !c.keyword_name()
+                    .is_some_and(|k| re.is_match(k.trim_start_matches('!')))
+            })
+    });
+    if bread.crumbs.is_empty() {
+        None
+    } else {
+        Some(bread)
+    }
+}
+
+/// sort the crumbs in every bread, then sort the breads by their first crumb
+fn sort_breads(breads: &mut [Bread], sort: SortBy, severities: &SeverityTable) {
+    match sort {
+        SortBy::Age => {
+            // crumbs without blame go last
+            let age_key = |c: &Crumb| c.blame.as_ref().map_or(i64::MAX, |b| b.timestamp);
+            breads
+                .iter_mut()
+                .for_each(|b| b.crumbs.sort_by_key(|c| age_key(c)));
+            breads.sort_by_key(|b| b.crumbs.first().map_or(i64::MAX, age_key));
+        }
+        SortBy::Severity => {
+            let severity_key = |c: &Crumb| Reverse(severities.get(c.keyword.as_deref()));
+            breads
+                .iter_mut()
+                .for_each(|b| b.crumbs.sort_by_key(|c| severity_key(c)));
+            breads.sort_by_key(|b| b.crumbs.first().map(severity_key));
+        }
+    }
+}
+
 /// make bread for this file
 fn bake_bread(file: &File, kwreg: &Option<Regex>, conf: &Config) -> Result<Option<Bread>> {
+    let file_p = file.to_string();
+    let use_cache = conf.cache.is_some();
+    let meta = if use_cache {
+        let meta = fs::metadata(&file.0)?;
+        if let Some(b) = cache::lookup(&file_p, &meta) {
+            return Ok(b);
+        }
+        Some(meta)
+    } else {
+        None
+    };
+
     // start to read file
     let mut buf = vec![];
-    let file_p = file.to_string();
     let mut f: std::fs::File = std::fs::File::open(file.0.clone())?;
     f.read_to_end(&mut buf)?;
 
+    let Some(meta) = meta else {
+        return Ok(bake_bread_from_content(file_p, &buf, file.1, kwreg, conf));
+    };
+
+    let hash = cache::content_hash(&buf);
+    if let Some(b) = cache::lookup_by_hash(&file_p, &meta, hash) {
+        return Ok(b);
+    }
+    let bread = bake_bread_from_content(file_p.clone(), &buf, file.1, kwreg, conf);
+    cache::store(&file_p, &meta, hash, &bread);
+    Ok(bread)
+}
+
+/// make bread from the content of file, `re` is the regex of this file's language
+fn bake_bread_from_content(
+    file_p: String,
+    buf: &[u8],
+    re: &Regex,
+    kwreg: &Option<Regex>,
+    conf: &Config,
+) -> Option<Bread> {
+    let result = if notebook::is_notebook(&file_p) {
+        bake_notebook_crumbs(&file_p, buf, re, kwreg, conf)
+    } else {
+        let plain_re = Path::new(&file_p)
+            .extension()
+            .and_then(|e| compat_regex(e.to_str()?, conf));
+        bake_crumbs(buf, re, plain_re.as_ref(), kwreg, conf)
+    };
+
+    if result.is_empty() {
+        None
+    } else {
+        Some(Bread::new(file_p, result))
+    }
+}
+
+/// the regex of plain keyword comments of the language if compat mode is on
+fn compat_regex(language: &str, conf: &Config) -> Option<Regex> {
+    if conf.compat {
+        COMPAT_REGEX_TABLE.lock().unwrap().get(language).cloned()
+    } else {
+        None
+    }
+}
+
+/// make the crumbs of the cells in notebook. The code cells are scanned by
+/// the kernel language, or `re` if the language isn't in table. The markdown
+/// cells are scanned by the markdown comment
+fn bake_notebook_crumbs(
+    file_p: &str,
+    buf: &[u8],
+    re: &Regex,
+    kwreg: &Option<Regex>,
+    conf: &Config,
+) -> Vec<Crumb> {
+    let text = String::from_utf8_lossy(buf);
+    let nb = match Notebook::parse(&text) {
+        Ok(nb) => nb,
+        Err(e) => {
+            debug!("notebook {} had error {}", file_p, e);
+            return vec![];
+        }
+    };
+
+    let (code_re, code_language) = {
+        let table = REGEX_TABLE.lock().unwrap();
+        nb.languages()
+            .iter()
+            .find_map(|l| Some((table.get(l)?.clone(), l.as_str())))
+            .unwrap_or((re.clone(), notebook::EXTENSION))
+    };
+    let code_plain = compat_regex(code_language, conf);
+    let md_re = REGEX_TABLE.lock().unwrap().get("md").cloned();
+    let md_plain = compat_regex("md", conf);
+
+    let mut result = vec![];
+    for cell in nb.cells() {
+        let (re, plain_re) = match cell.kind {
+            CellKind::Code => (Some(&code_re), code_plain.as_ref()),
+            CellKind::Markdown => (md_re.as_ref(), md_plain.as_ref()),
+            CellKind::Raw => (None, None),
+        };
+        if let Some(re) = re {
+            result.extend(
+                bake_crumbs(cell.source.as_bytes(), re, plain_re, kwreg, conf)
+                    .into_iter()
+                    .map(|c| c.in_cell(cell.index)),
+            );
+        }
+    }
+    result
+}
+
+/// make the crumbs of lines in buf, `plain_re` is the regex of plain keyword
+/// comments in compat mode
+fn bake_crumbs(
+    buf: &[u8],
+    re: &Regex,
+    plain_re: Option<&Regex>,
+    kwreg: &Option<Regex>,
+    conf: &Config,
+) -> Vec<Crumb> {
     let mut line_num = 0;
     let mut ss = String::new(); // temp
-    let mut buf = buf.as_slice();
+    let mut buf = buf;
     let mut result = vec![];
-    let mut head: Option<Crumb> = None; // for tail support
+    let mut head: Option<(Crumb, usize)> = None; // for tail support, with its content indent
     let mut shadow_file = vec![]; // the copy of file for later range operation 
 
     // closure for keywords feature
     let mut keyword_checker_and_push = |mut cb: Crumb| {
-        if kwreg.is_some() {
+        if conf.no_keyword {
+            // only the crumbs without keyword
+            let has_keyword = match kwreg {
+                Some(re) => re.is_match(&cb.content),
+                None => cb.has_keyword_prefix(),
+            };
+            if !has_keyword && (!cb.is_ignore() || conf.show_ignored) {
+                result.push(cb)
+            }
+        } else if kwreg.is_some() {
             // filter_keywords will update keyword even the crumb is ignored
             if cb.filter_keywords(kwreg.as_ref().unwrap()) {
+                cb.keyword = cb.keyword.map(|k| conf.keywords.canonical(&k).to_string());
                 result.push(cb)
             }
         } else {
+            // nothing filtered, the defined keywords are still known
+            if let Some(re) = &conf.defined_keywords
+                && cb.filter_keywords(re)
+            {
+                cb.keyword = cb.keyword.map(|k| conf.keywords.canonical(&k).to_string());
+            }
             if !cb.is_ignore() || conf.show_ignored {
                 result.push(cb)
             }
@@ -234,42 +577,37 @@ fn bake_bread(file: &File, kwreg: &Option<Regex>, conf: &Config) -> Result<Optio
         line_num += 1;
         match buf.read_line(&mut ss) {
             Ok(0) | Err(_) => {
-                if head.is_some() {
-                    keyword_checker_and_push(head.unwrap());
+                if let Some((h, _)) = head.take() {
+                    keyword_checker_and_push(h);
                 }
                 break; // if EOF or any error in this file, break
             }
-            Ok(_) => match filter_line(&ss, line_num, file.1) {
+            Ok(_) => match filter_line(&ss, line_num, re)
+                .or_else(|| plain_re.and_then(|p| filter_plain_line(&ss, line_num, p)))
+            {
                 Some(cb) => {
+                    let indent = content_indent(&ss, &cb);
                     // check head first
-                    match head {
-                        Some(ref mut h) => {
-                            if h.has_tail() {
-                                // if head has tail, add this line to head, continue
-                                h.add_tail(cb);
-                                ss.clear(); // before continue, clear temp
-                                continue;
-                            } else {
-                                // store head
-                                keyword_checker_and_push(head.unwrap());
-                                head = None;
+                    match head.take() {
+                        Some((mut h, head_indent))
+                            if is_tail(&h, head_indent, &cb, indent, kwreg, conf.group_mode) =>
+                        {
+                            h.add_tail(cb);
+                            head = Some((h, head_indent));
+                        }
+                        last => {
+                            // store head
+                            if let Some((h, _)) = last {
+                                keyword_checker_and_push(h);
                             }
+                            // every crumb can be the head of next lines
+                            head = Some((cb, indent));
                         }
-                        None => (),
-                    }
-
-                    if cb.has_tail() {
-                        // make new head
-                        head = Some(cb);
-                    } else {
-                        // store result
-                        keyword_checker_and_push(cb)
                     }
                 }
                 None => {
-                    if head.is_some() {
-                        keyword_checker_and_push(head.unwrap());
-                        head = None;
+                    if let Some((h, _)) = head.take() {
+                        keyword_checker_and_push(h);
                     }
                 }
             },
@@ -302,19 +640,31 @@ fn bake_bread(file: &File, kwreg: &Option<Regex>, conf: &Config) -> Result<Optio
         });
     }
 
-    if result.len() == 0 {
-        Ok(None)
-    } else {
-        Ok(Some(Bread::new(file_p, result)))
-    }
+    result
+}
+
+/// the cell of notebook (None in other files) and the line number
+type LineKey = (Option<usize>, usize);
+
+/// the line keys and the positions of all lines of the crumb
+fn line_positions(crumb: &Crumb) -> impl Iterator<Item = (LineKey, usize)> {
+    crumb
+        .all_lines_num_postion_pair()
+        .into_iter()
+        .map(|(line_num, pos)| ((crumb.cell, line_num), pos))
+}
+
+/// the line keys, the positions, the headers and the contents of all lines of the crumb
+fn line_rewrites(crumb: &Crumb) -> impl Iterator<Item = (LineKey, usize, &str, &str)> {
+    crumb
+        .all_lines_num_postion_and_header_content()
+        .into_iter()
+        .map(|(line_num, pos, header, content)| ((crumb.cell, line_num), pos, header, content))
 }
 
 /// delete crumbs and re-write the file
 pub fn delete_the_crumbs(Bread { file_path, crumbs }: Bread) -> Result<String> {
-    let all_delete_line_postion_pairs = crumbs
-        .iter()
-        .map(|crumb| crumb.all_lines_num_postion_pair())
-        .flatten();
+    let all_delete_line_postion_pairs = crumbs.iter().flat_map(line_positions);
 
     delete_lines_on(&file_path, all_delete_line_postion_pairs)?;
 
@@ -323,149 +673,322 @@ pub fn delete_the_crumbs(Bread { file_path, crumbs }: Bread) -> Result<String> {
 }
 
 /// delete crumbs by special indexes
-pub fn delete_the_crumbs_on_special_index(
+pub fn delete_the_crumbs_on_special_index(bread: Bread, indexes: HashSet<usize>) -> Result<String> {
+    let file_path = delete_crumbs_on_indexes(bread, &indexes)?;
+
+    println!("deleted {} crumbs in {}", indexes.len(), file_path);
+
+    Ok(file_path)
+}
+
+/// delete crumbs by special indexes without printing,
+/// for the servers those stdout is their channel
+pub(crate) fn delete_crumbs_on_indexes(
     Bread { file_path, crumbs }: Bread,
-    indexes: HashSet<usize>,
+    indexes: &HashSet<usize>,
 ) -> Result<String> {
     let mut all_delete_lines = vec![];
-    for ind in &indexes {
+    for ind in indexes {
         match crumbs.get(*ind) {
-            Some(c) => all_delete_lines.append(&mut c.all_lines_num_postion_pair()),
+            Some(c) => all_delete_lines.extend(line_positions(c)),
             None => return Err(io::Error::other("cannot find crumb index in bread")),
         }
     }
 
     delete_lines_on(&file_path, all_delete_lines.into_iter())?;
-
-    println!("deleted {} crumbs in {}", indexes.len(), file_path);
-
     Ok(file_path)
 }
 
 /// delete special lines of the file on file_path
 fn delete_lines_on(
     file_path: &str,
-    line_num_pos_pairs: impl Iterator<Item = (usize, usize)>,
+    line_pos_pairs: impl Iterator<Item = (LineKey, usize)>,
 ) -> Result<()> {
-    let f = fs::File::open(&file_path)?;
-    let reader = BufReader::new(f).lines();
-
-    let all_delete_lines = line_num_pos_pairs.collect();
+    let all_delete_lines: HashMap<LineKey, usize> = line_pos_pairs.collect();
 
-    let finish_deleted = delete_nth_lines(reader, all_delete_lines)?
-        .into_iter()
-        .map(|line| line.into_bytes());
+    edit_crumb_lines_on(file_path, |key, line| match all_delete_lines.get(&key) {
+        Some(pos) => delete_crumb_in_line(&line, *pos),
+        None => Some(line),
+    })
+}
 
-    let mut new_file = OpenOptions::new()
-        .write(true)
-        .truncate(true)
-        .open(file_path)?;
+/// the range of the closer (with the spaces before it) of the block
+/// comment starting at `pos`, like ` -->` of `<!--:= crumb -->`
+fn closer_range(line: &str, pos: usize) -> Option<Range<usize>> {
+    let comment = line.get(pos..)?;
+    let closer = comment_closer(comment)?;
+    // the closer is after the symbols, `<!-->` isn't closed by the `-->`
+    let start = comment.len()
+        - comment
+            .trim_start_matches(|c: char| !c.is_alphanumeric() && !c.is_whitespace())
+            .len();
+    let end = start + comment[start..].find(closer)?;
+    Some(pos + comment[..end].trim_end().len()..pos + end + closer.len())
+}
 
-    for line in finish_deleted {
-        new_file.write_all(&line)?;
-        new_file.write_all(b"\n")?
+/// the line after the crumb starting at `pos` deleted, None if nothing
+/// left and this line should be removed. The indentation is kept
+pub(crate) fn delete_crumb_in_line(line: &str, pos: usize) -> Option<String> {
+    let mut new_l = line[..pos.min(line.len())].to_string();
+    // the code after the block comment is kept
+    if let Some(r) = closer_range(line, pos) {
+        new_l.push_str(&line[r.end..]);
+    }
+    if new_l.is_empty() {
+        // empty line just skip
+        None
+    } else {
+        Some(new_l)
     }
-    Ok(())
 }
 
-/// delete crumbs of file, return the new file contents without the crumbs deleted
-fn delete_nth_lines(
-    f: impl Iterator<Item = Result<String>>,
-    nm: HashMap<usize, usize>,
-) -> Result<Vec<String>> {
-    let mut result = vec![];
-
-    for (line_num, ll) in f.enumerate() {
-        if nm.contains_key(&(line_num + 1)) {
-            let mut new_l = ll?;
-            new_l.truncate(*nm.get(&(line_num + 1)).unwrap());
-            if new_l == "" {
-                // empty line just skip
-                continue;
-            }
-            result.push(new_l);
-        } else {
-            result.push(ll?);
-        }
+/// the line after the crumb starting at `pos` restored to normal comment
+pub(crate) fn restore_crumb_in_line(line: &str, pos: usize, header: &str, content: &str) -> String {
+    let mut new_l = line[..pos.min(line.len())].to_string();
+    new_l.push_str(header);
+    // the header of plain text is only the indentation
+    if !header.trim().is_empty() {
+        new_l.push(' ');
     }
+    new_l.push_str(content);
+    if let Some(r) = closer_range(line, pos) {
+        new_l.push_str(&line[r.start..]);
+    }
+    new_l
+}
 
-    Ok(result)
+/// the line after the plain comment starting at `pos` adopted to crumb
+pub(crate) fn adopt_crumb_in_line(line: &str, pos: usize, header: &str, content: &str) -> String {
+    let mut new_l = line[..pos.min(line.len())].to_string();
+    new_l.push_str(header);
+    new_l.push_str(":= ");
+    new_l.push_str(content);
+    if let Some(r) = closer_range(line, pos) {
+        new_l.push_str(&line[r.start..]);
+    }
+    new_l
 }
 
 /// restore the bread's crumb to normal comment
 pub fn restore_the_crumb(Bread { file_path, crumbs }: Bread) -> Result<String> {
-    let all_restore_lines = crumbs
-        .iter()
-        .map(|c| c.all_lines_num_postion_and_header_content())
-        .flatten();
+    let all_restore_lines = crumbs.iter().flat_map(line_rewrites);
 
-    restore_lines_on(&file_path, all_restore_lines)?;
+    rewrite_lines_on(&file_path, all_restore_lines, restore_crumb_in_line)?;
 
     println!("restored the crumbs in {}", file_path);
     Ok(file_path)
 }
 
 /// restore the bread's crumb by special indexes
-pub fn restore_the_crumb_on_special_index(
+pub fn restore_the_crumb_on_special_index(bread: Bread, indexes: HashSet<usize>) -> Result<String> {
+    let file_path = restore_crumbs_on_indexes(bread, &indexes)?;
+
+    println!("restored {} crumbs in {}", indexes.len(), file_path);
+    Ok(file_path)
+}
+
+/// restore the bread's crumb by special indexes without printing,
+/// for the servers those stdout is their channel
+pub(crate) fn restore_crumbs_on_indexes(
     Bread { file_path, crumbs }: Bread,
-    indexes: HashSet<usize>,
+    indexes: &HashSet<usize>,
 ) -> Result<String> {
     let mut all_restore_lines = Vec::with_capacity(indexes.len());
-    for ind in &indexes {
+    for ind in indexes {
         match crumbs.get(*ind) {
-            Some(c) => all_restore_lines.append(&mut c.all_lines_num_postion_and_header_content()),
+            Some(c) => all_restore_lines.extend(line_rewrites(c)),
             None => return Err(io::Error::other("cannot find crumb index in bread")),
         }
     }
 
-    restore_lines_on(&file_path, all_restore_lines.into_iter())?;
-
-    println!("restored {} crumbs in {}", indexes.len(), file_path);
+    rewrite_lines_on(
+        &file_path,
+        all_restore_lines.into_iter(),
+        restore_crumb_in_line,
+    )?;
     Ok(file_path)
 }
 
-fn restore_lines_on<'a>(
-    file_path: &'a str,
-    all_restore_lines: impl Iterator<Item = (usize, usize, &'a str, &'a str)>,
-) -> Result<()> {
-    let f = fs::File::open(&file_path)?;
-    let reader = BufReader::new(f).lines();
+/// adopt the plain comments of bread by special indexes to crumbs
+pub fn adopt_the_crumbs_on_special_index(bread: Bread, indexes: HashSet<usize>) -> Result<String> {
+    let file_path = adopt_crumbs_on_indexes(bread, &indexes)?;
 
-    let mut table: HashMap<usize, (usize, &str, &str)> =
-        HashMap::with_capacity(all_restore_lines.size_hint().1.unwrap_or(0));
+    println!("adopted {} crumbs in {}", indexes.len(), file_path);
+    Ok(file_path)
+}
 
-    all_restore_lines.for_each(|(line_num, pos, header, content)| {
-        table.insert(line_num, (pos, header, content));
-    });
+/// adopt the plain comments of bread by special indexes without printing
+pub(crate) fn adopt_crumbs_on_indexes(
+    Bread { file_path, crumbs }: Bread,
+    indexes: &HashSet<usize>,
+) -> Result<String> {
+    let mut all_adopt_lines = Vec::with_capacity(indexes.len());
+    for ind in indexes {
+        match crumbs.get(*ind) {
+            Some(c) => all_adopt_lines.extend(line_rewrites(c)),
+            None => return Err(io::Error::other("cannot find crumb index in bread")),
+        }
+    }
+
+    rewrite_lines_on(&file_path, all_adopt_lines.into_iter(), adopt_crumb_in_line)?;
+    Ok(file_path)
+}
 
-    let mut new_file = Vec::with_capacity(reader.size_hint().1.unwrap_or(0));
+/// rewrite every line (with its line number) of file by `edit`,
+/// the line is removed if `edit` gives None
+pub(crate) fn edit_lines_on(
+    file_path: &str,
+    mut edit: impl FnMut(usize, String) -> Option<String>,
+) -> Result<()> {
+    let reader = BufReader::new(fs::File::open(file_path)?).lines();
+    let mut new_file = vec![];
     for (line_num, ll) in reader.enumerate() {
-        if let Some((pos, header, content)) = table.get(&(line_num + 1)) {
-            let mut new_l = ll?;
-            new_l.truncate(*pos);
-            new_l.push_str(*header);
-            new_l.push_str(" ");
-            new_l.push_str(*content);
-
-            new_file.push(new_l.into_bytes())
-        } else {
-            new_file.push(ll?.into_bytes());
-        }
+        new_file.extend(edit(line_num + 1, ll?));
     }
 
     let mut file = OpenOptions::new()
         .write(true)
         .truncate(true)
         .open(file_path)?;
-
     for line in new_file {
-        file.write_all(&line)?;
+        file.write_all(line.as_bytes())?;
         file.write_all(b"\n")?
     }
-
     Ok(())
 }
 
+/// rewrite every line of file by `edit` like `edit_lines_on`, which gets the
+/// line key. The lines of notebook are the lines in its cells
+fn edit_crumb_lines_on(
+    file_path: &str,
+    mut edit: impl FnMut(LineKey, String) -> Option<String>,
+) -> Result<()> {
+    if notebook::is_notebook(file_path) {
+        notebook::edit_lines_on(file_path, |cell, line_num, line| {
+            edit((Some(cell), line_num), line)
+        })
+    } else {
+        edit_lines_on(file_path, |line_num, line| edit((None, line_num), line))
+    }
+}
+
+/// rewrite the special lines of the file by `rewrite`, which gets the line,
+/// the position, the header and the content
+fn rewrite_lines_on<'a>(
+    file_path: &'a str,
+    all_lines: impl Iterator<Item = (LineKey, usize, &'a str, &'a str)>,
+    rewrite: fn(&str, usize, &str, &str) -> String,
+) -> Result<()> {
+    let mut table: HashMap<LineKey, (usize, &str, &str)> =
+        HashMap::with_capacity(all_lines.size_hint().1.unwrap_or(0));
+
+    all_lines.for_each(|(key, pos, header, content)| {
+        table.insert(key, (pos, header, content));
+    });
+
+    edit_crumb_lines_on(file_path, |key, line| match table.get(&key) {
+        Some((pos, header, content)) => Some(rewrite(&line, *pos, header, content)),
+        None => Some(line),
+    })
+}
+
+/// add the crumb of text at the line (starts from 1) of file. It is the new
+/// line above that line with the same indentation, or appended to the end
+/// of that line
+pub fn add_crumb(path: &Path, line_num: usize, text: &str, append: bool) -> Result<String> {
+    let file_path = path.to_string_lossy().to_string();
+    let language = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default();
+    let (Some(symbol), Some(re)) = (
+        comment_symbol(language),
+        REGEX_TABLE.lock().unwrap().get(language).cloned(),
+    ) else {
+        return Err(io::Error::other(format!(
+            "unsupported file type {}",
+            file_path
+        )));
+    };
+    if text.trim().is_empty() || text.contains('\n') {
+        return Err(io::Error::other("the crumb should be one non-empty line"));
+    }
+    if notebook::is_notebook(path) {
+        return Err(io::Error::other(format!(
+            "cannot add the crumb to notebook {}",
+            file_path
+        )));
+    }
+
+    let content = fs::read_to_string(path)?;
+    // the lines and their endings, the endings are written back as they are
+    let mut lines = content
+        .split_inclusive('\n')
+        .map(|l| {
+            let body = l
+                .strip_suffix("\r\n")
+                .or_else(|| l.strip_suffix('\n'))
+                .unwrap_or(l);
+            (body.to_string(), &l[body.len()..])
+        })
+        .collect::<Vec<_>>();
+    // the new line can be added after the last line
+    let max_line = if append { lines.len() } else { lines.len() + 1 };
+    if line_num == 0 || line_num > max_line {
+        return Err(io::Error::other(format!(
+            "line {} is out of {} ({} lines)",
+            line_num,
+            file_path,
+            lines.len()
+        )));
+    }
+
+    let crumb = match comment_closer(&symbol) {
+        Some(closer) => format!("{}:= {} {}", symbol, text.trim(), closer),
+        None => format!("{}:= {}", symbol, text.trim()),
+    };
+    let new_line = match lines.get(line_num - 1).map(|(l, _)| l) {
+        Some(l) if append && l.trim().is_empty() => format!("{}{}", l, crumb),
+        Some(l) if append => format!("{} {}", l, crumb),
+        Some(l) => format!("{}{}", &l[..l.len() - l.trim_start().len()], crumb),
+        None => crumb,
+    };
+    // the comment symbol in table may not make the crumb, like `/*`
+    if !re.is_match(&new_line) {
+        return Err(io::Error::other(format!(
+            "cannot make the crumb by comment symbol {}",
+            symbol
+        )));
+    }
+
+    // the inserted line ends like the nearest lines around it
+    let (before, after) = lines.split_at(line_num.saturating_sub(2));
+    let newline = after
+        .iter()
+        .chain(before.iter().rev())
+        .map(|(_, e)| *e)
+        .find(|e| !e.is_empty())
+        .unwrap_or("\n");
+    if append {
+        lines[line_num - 1].0 = new_line
+    } else if line_num > lines.len()
+        && let Some(last) = lines.last_mut().filter(|(_, e)| e.is_empty())
+    {
+        // the file without the ending still has none after the new last line
+        last.1 = newline;
+        lines.push((new_line, ""))
+    } else {
+        lines.insert(line_num - 1, (new_line, newline))
+    }
+
+    let mut file = OpenOptions::new().write(true).truncate(true).open(path)?;
+    for (line, ending) in lines {
+        file.write_all(line.as_bytes())?;
+        file.write_all(ending.as_bytes())?
+    }
+    Ok(file_path)
+}
+
 /// run format command with filepath input
 pub fn run_format_command_to_file(
     fmt_command: &str,
@@ -490,10 +1013,10 @@ pub fn run_format_command_to_file(
         .map_err(|e| e.to_string())
 }
 
-/// entry function of main logic
-pub fn handle_files(conf: Config) -> impl Iterator<Item = Bread> {
+/// split all files in arguments to groups for threads
+fn files_groups(conf: &Config) -> Vec<Vec<File>> {
     // first add all files in arguments
-    let mut all_files: Vec<File> = files_in_dir_or_file_vec(&conf.files, &conf).unwrap();
+    let mut all_files: Vec<File> = files_in_dir_or_file_vec(&conf.files, conf).unwrap();
 
     // split to groups
     let threads_num: usize = thread::available_parallelism()
@@ -507,9 +1030,18 @@ pub fn handle_files(conf: Config) -> impl Iterator<Item = Bread> {
         groups.push(all_files.drain(0..count).collect())
     }
     groups.push(all_files.drain(0..).collect());
+    groups
+}
+
+/// entry function of main logic
+pub fn handle_files(conf: Config) -> impl Iterator<Item = Bread> {
+    let groups = files_groups(&conf);
+    let sort = conf.sort;
+    let severities = conf.severities.clone();
+    cache::load(&conf);
 
     let conf = Arc::new(RwLock::new(conf));
-    groups
+    let mut breads = groups
         .into_iter()
         .map(move |fs| {
             let kwreg = KEYWORDS_REGEX.lock().unwrap().clone();
@@ -522,6 +1054,45 @@ pub fn handle_files(conf: Config) -> impl Iterator<Item = Bread> {
         })
         .map(|han| han.join().unwrap())
         .flatten()
+        .collect::<Vec<_>>();
+    cache::save();
+
+    if let Some(sort) = sort {
+        sort_breads(&mut breads, sort, &severities)
+    }
+    breads.into_iter()
+}
+
+/// like `handle_files`, but give the bread as soon as any thread finished it.
+/// The order of breads is not stable.
+pub fn stream_files(conf: Config) -> impl Iterator<Item = Bread> {
+    let groups = files_groups(&conf);
+    let (sender, receiver) = mpsc::channel();
+    cache::load(&conf);
+
+    let conf = Arc::new(RwLock::new(conf));
+    let kwreg = KEYWORDS_REGEX.lock().unwrap().clone();
+    for fs in groups {
+        let kwreg = kwreg.clone();
+        let conf_c = Arc::clone(&conf);
+        let sender = sender.clone();
+        thread::spawn(move || {
+            for f in fs {
+                if let Some(b) = op_file(f, &kwreg, conf_c.clone()).unwrap()
+                    && sender.send(b).is_err()
+                {
+                    // receiver is gone, no need to keep going
+                    break;
+                }
+            }
+        });
+    }
+
+    receiver.into_iter().chain(std::iter::from_fn(|| {
+        // all breads are sent
+        cache::save();
+        None
+    }))
 }
 
 #[cfg(test)]
@@ -537,6 +1108,57 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_content_indent_and_tail() {
+        let re = Regex::new(r"(//+):=\s+(.*)").unwrap();
+        let head = filter_line("//:= TODO: a\n", 1, &re).unwrap();
+        let tail = filter_line("//:=   b\n", 2, &re).unwrap();
+        assert_eq!(content_indent("//:= TODO: a\n", &head), 1);
+        assert_eq!(content_indent("//:=   b\n", &tail), 3);
+
+        assert!(!is_tail(&head, 1, &tail, 3, &None, GroupMode::Explicit));
+        assert!(is_tail(&head, 1, &tail, 3, &None, GroupMode::Consecutive));
+        assert!(is_tail(&head, 1, &tail, 3, &None, GroupMode::Indent));
+        assert!(!is_tail(&head, 1, &tail, 1, &None, GroupMode::Indent));
+
+        // the new keyword and the different column
+        let mark = filter_line("//:= MARK: c\n", 2, &re).unwrap();
+        assert!(!is_tail(&head, 1, &mark, 1, &None, GroupMode::Consecutive));
+        let moved = filter_line("  //:= b\n", 2, &re).unwrap();
+        assert!(!is_tail(&head, 1, &moved, 1, &None, GroupMode::Consecutive));
+    }
+
+    #[test]
+    fn test_rewrite_block_comment() {
+        let line = "<p/> <!--:= TODO: a --> <b/>";
+        assert_eq!(closer_range(line, 5), Some(19..23));
+        assert_eq!(
+            delete_crumb_in_line(line, 5),
+            Some("<p/>  <b/>".to_string())
+        );
+        assert_eq!(
+            restore_crumb_in_line(line, 5, "<!--", "TODO: a"),
+            "<p/> <!-- TODO: a --> <b/>"
+        );
+        assert_eq!(delete_crumb_in_line("<!--:= TODO: a -->", 0), None);
+        // the indentation is left like other crumbs
+        assert_eq!(
+            delete_crumb_in_line("  <!--:= TODO: a -->", 2),
+            Some("  ".to_string())
+        );
+        assert_eq!(
+            delete_crumb_in_line("    //:= a", 4),
+            Some("    ".to_string())
+        );
+
+        // the block comment without closer in this line
+        assert_eq!(closer_range("<!--:= TODO: a...", 0), None);
+        assert_eq!(closer_range("<!-->", 0), None);
+
+        // the plain text crumb, the header is the indentation
+        assert_eq!(restore_crumb_in_line("  := b", 0, "  ", "b"), "  b");
+    }
+
     // #[test]
     // fn test_available_parallelism_on_my_machine() {
     //     dbg!(thread::available_parallelism().unwrap());
@@ -0,0 +1,473 @@
+//! The interactive prompt of delete, restore and adopt.
+//!
+//! The prompt is the state machine walking all breads, bread by bread or
+//! crumb by crumb. The answers are only the choices, nothing is changed
+//! until the chosen crumbs are confirmed at the end.
+
+use super::datatypes::*;
+use super::fs_operation;
+use super::output::ChangeSummary;
+use std::collections::{BTreeMap, HashSet};
+
+/// where the prompt is
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum State {
+    /// ask for all breads at once
+    All,
+    /// ask for the whole bread
+    Bread(usize),
+    /// ask for one crumb of the bread
+    Crumb(usize, usize),
+    /// confirm the chosen crumbs before applying them
+    Confirm,
+}
+
+/// the choices made so far
+#[derive(Debug, Clone, Default)]
+struct Choice {
+    /// the chosen crumb indexes of every bread
+    chosen: Vec<HashSet<usize>>,
+    /// the keywords chosen (true) or skipped (false) in bulk, they win over
+    /// the single crumb choices
+    keywords: BTreeMap<String, bool>,
+}
+
+/// what to do after the answer
+#[derive(Debug, PartialEq)]
+enum Step {
+    /// show the current state and ask
+    Show,
+    /// ask again without showing
+    Ask,
+    /// apply the chosen crumbs
+    Apply,
+    /// quit without changes
+    Quit,
+}
+
+struct Prompt<'a> {
+    breads: &'a [Bread],
+    /// delete, restore or adopt
+    op: &'a str,
+    state: State,
+    choice: Choice,
+    /// the states and choices before every answer, for going back
+    history: Vec<(State, Choice)>,
+}
+
+impl<'a> Prompt<'a> {
+    fn new(breads: &'a [Bread], op: &'a str) -> Self {
+        Self {
+            breads,
+            op,
+            state: State::All,
+            choice: Choice {
+                chosen: vec![HashSet::new(); breads.len()],
+                ..Default::default()
+            },
+            history: vec![],
+        }
+    }
+
+    fn is_chosen(&self, b: usize, c: usize) -> bool {
+        match self.breads[b].crumbs[c]
+            .keyword_name()
+            .and_then(|k| self.choice.keywords.get(k))
+        {
+            Some(v) => *v,
+            None => self.choice.chosen[b].contains(&c),
+        }
+    }
+
+    /// the chosen crumb indexes of every bread
+    fn chosen(&self) -> Vec<HashSet<usize>> {
+        (0..self.breads.len())
+            .map(|b| {
+                (0..self.breads[b].crumbs.len())
+                    .filter(|c| self.is_chosen(b, *c))
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn tally(&self) -> usize {
+        self.chosen().iter().map(|c| c.len()).sum()
+    }
+
+    /// the first crumb from `c` in bread `b` which isn't chosen by keyword
+    fn next_crumb(&self, b: usize, c: usize) -> Option<usize> {
+        (c..self.breads[b].crumbs.len()).find(|c| {
+            self.breads[b].crumbs[*c]
+                .keyword_name()
+                .is_none_or(|k| !self.choice.keywords.contains_key(k))
+        })
+    }
+
+    /// the state after bread `b`
+    fn after_bread(&self, b: usize) -> State {
+        if b + 1 < self.breads.len() {
+            State::Bread(b + 1)
+        } else {
+            State::Confirm
+        }
+    }
+
+    /// the state after crumb `c` of bread `b`
+    fn after_crumb(&self, b: usize, c: usize) -> State {
+        match self.next_crumb(b, c + 1) {
+            Some(c) => State::Crumb(b, c),
+            None => self.after_bread(b),
+        }
+    }
+
+    fn choose_crumbs(&mut self, b: usize, crumbs: impl Iterator<Item = usize>, yes: bool) {
+        let chosen = &mut self.choice.chosen[b];
+        for c in crumbs {
+            if yes {
+                chosen.insert(c);
+            } else {
+                chosen.remove(&c);
+            }
+        }
+    }
+
+    /// choose all crumbs of the breads from `b`
+    fn choose_breads(&mut self, b: usize) {
+        for b in b..self.breads.len() {
+            self.choose_crumbs(b, 0..self.breads[b].crumbs.len(), true);
+        }
+    }
+
+    fn question(&self) -> String {
+        let tally = format!("[{} to {}] ", self.tally(), self.op);
+        match self.state {
+            State::All => format!(
+                "{}Are you sure you want to {} all crumbs? (y/n/s/i/?): ",
+                tally, self.op
+            ),
+            State::Bread(b) => format!(
+                "{}Are you sure you want to {} this bread {}? (y/n/i/a/b/s/q/x/?): ",
+                tally, self.op, self.breads[b].file_path
+            ),
+            State::Crumb(..) => format!(
+                "{}Are you sure you want to {} this crumb? (y/n/a/f/Y/N/b/s/q/x/?): ",
+                tally, self.op
+            ),
+            State::Confirm => format!("{}Apply the changes? (y/n/b/s/?): ", tally),
+        }
+    }
+
+    fn help(&self) -> String {
+        let op = self.op;
+        match self.state {
+            State::All => format!(
+                "y: {op} all crumbs, n: {op} nothing, s: show again, \
+                 i: choose bread by bread"
+            ),
+            State::Bread(_) => format!(
+                "y: {op} this bread, n: skip this bread, i: choose crumb by crumb, \
+                 a: {op} this and all remaining breads, b: go back, s: show again, \
+                 q: stop and apply what is chosen, x: quit without changes"
+            ),
+            State::Crumb(..) => format!(
+                "y: {op} this crumb, n: skip this crumb, \
+                 a: {op} this and the rest crumbs of this bread, f: skip the rest of this bread, \
+                 Y: {op} all crumbs with this keyword, N: skip all crumbs with this keyword, \
+                 b: go back, s: show again, q: stop and apply what is chosen, \
+                 x: quit without changes"
+            ),
+            State::Confirm => {
+                "y: apply the changes, n: quit without changes, b: go back, s: show again"
+                    .to_string()
+            }
+        }
+    }
+
+    fn show(&self) {
+        match self.state {
+            State::All => self.breads.iter().for_each(|b| println!("{}", b)),
+            State::Bread(b) => println!("{}", self.breads[b]),
+            State::Crumb(b, c) => println!("{}", self.breads[b].crumbs[c]),
+            State::Confirm => {
+                let breads = self
+                    .breads
+                    .iter()
+                    .zip(self.chosen())
+                    .map(|(b, chosen)| {
+                        let mut b = b.clone();
+                        b.crumbs = b
+                            .crumbs
+                            .into_iter()
+                            .enumerate()
+                            .filter(|(i, _)| chosen.contains(i))
+                            .map(|(_, c)| c)
+                            .collect();
+                        b
+                    })
+                    .filter(|b| !b.crumbs.is_empty())
+                    .collect::<Vec<_>>();
+                print!("{}", ChangeSummary::new(self.op, false, &breads))
+            }
+        }
+    }
+
+    /// move to the state, the current one is kept for going back
+    fn goto(&mut self, state: State, choice: Choice) -> Step {
+        self.history.push((self.state, choice));
+        self.state = state;
+        Step::Show
+    }
+
+    fn answer(&mut self, s: &str) -> Step {
+        let before = self.choice.clone();
+        let next = match (self.state, s.trim()) {
+            (_, "?") => {
+                println!("{}", self.help());
+                return Step::Ask;
+            }
+            (_, "s") => return Step::Show,
+            (_, "b") => match self.history.pop() {
+                Some((state, choice)) => {
+                    self.state = state;
+                    self.choice = choice;
+                    return Step::Show;
+                }
+                None => {
+                    println!("nothing to go back");
+                    return Step::Ask;
+                }
+            },
+            (State::Bread(_) | State::Crumb(..), "q") => State::Confirm,
+            (State::Bread(_) | State::Crumb(..), "x") => return Step::Quit,
+
+            (State::All, "y") => {
+                self.choose_breads(0);
+                return Step::Apply;
+            }
+            (State::All, "n") => return Step::Quit,
+            (State::All, "i") => State::Bread(0),
+
+            (State::Bread(b), "y") => {
+                self.choose_crumbs(b, 0..self.breads[b].crumbs.len(), true);
+                self.after_bread(b)
+            }
+            (State::Bread(b), "n") => {
+                self.choose_crumbs(b, 0..self.breads[b].crumbs.len(), false);
+                self.after_bread(b)
+            }
+            (State::Bread(b), "i") => match self.next_crumb(b, 0) {
+                Some(c) => State::Crumb(b, c),
+                None => self.after_bread(b),
+            },
+            (State::Bread(b), "a") => {
+                self.choose_breads(b);
+                State::Confirm
+            }
+
+            (State::Crumb(b, c), "y" | "n") => {
+                self.choose_crumbs(b, std::iter::once(c), s.trim() == "y");
+                self.after_crumb(b, c)
+            }
+            (State::Crumb(b, c), "a" | "f") => {
+                self.choose_crumbs(b, c..self.breads[b].crumbs.len(), s.trim() == "a");
+                self.after_bread(b)
+            }
+            (State::Crumb(b, c), "Y" | "N") => match self.breads[b].crumbs[c].keyword_name() {
+                Some(k) => {
+                    self.choice.keywords.insert(k.to_string(), s.trim() == "Y");
+                    self.after_crumb(b, c)
+                }
+                None => {
+                    println!("this crumb doesn't have keyword");
+                    return Step::Ask;
+                }
+            },
+
+            (State::Confirm, "y") => return Step::Apply,
+            (State::Confirm, "n") => return Step::Quit,
+
+            _ => {
+                println!("I don't understand, give ? for help");
+                return Step::Ask;
+            }
+        };
+
+        if next == State::Confirm && self.tally() == 0 {
+            println!("nothing to {}", self.op);
+            return Step::Quit;
+        }
+        self.goto(next, before)
+    }
+
+    /// ask until applying or quitting, true if applying
+    fn run(
+        &mut self,
+        mut read: impl FnMut(&str) -> Result<String, String>,
+    ) -> Result<bool, String> {
+        let mut step = Step::Show;
+        loop {
+            match step {
+                Step::Show => self.show(),
+                Step::Ask => (),
+                Step::Apply => return Ok(true),
+                Step::Quit => return Ok(false),
+            }
+            step = self.answer(&read(&self.question())?);
+        }
+    }
+}
+
+/// prompt the breads to delete, restore or adopt, the chosen crumbs are applied
+/// after the confirmation. Return the changed files
+pub(crate) fn prompt_breads(
+    breads: Vec<Bread>,
+    op: &str,
+    read: impl FnMut(&str) -> Result<String, String>,
+) -> Result<Option<HashSet<String>>, String> {
+    if breads.is_empty() {
+        return Ok(None);
+    }
+
+    let mut prompt = Prompt::new(&breads, op);
+    if !prompt.run(read)? {
+        return Ok(None);
+    }
+    let chosen = prompt.chosen();
+
+    let mut files_changed = HashSet::new();
+    for (b, indexes) in breads.into_iter().zip(chosen) {
+        if indexes.is_empty() {
+            continue;
+        }
+        let file_path = match op {
+            "delete" => fs_operation::delete_the_crumbs_on_special_index(b, indexes),
+            "adopt" => fs_operation::adopt_the_crumbs_on_special_index(b, indexes),
+            _ => fs_operation::restore_the_crumb_on_special_index(b, indexes),
+        };
+        files_changed.insert(file_path.map_err(|e| e.to_string())?);
+    }
+    Ok(Some(files_changed).filter(|f| !f.is_empty()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn breads() -> Vec<Bread> {
+        let crumb =
+            |line, content: &str| Crumb::new(line, 0, content.to_string(), "//".to_string());
+        vec![
+            Bread::new(
+                "a.rs".to_string(),
+                vec![
+                    crumb(1, "TODO: one"),
+                    crumb(2, "FIXME: two"),
+                    crumb(3, "TODO: three"),
+                ],
+            ),
+            Bread::new(
+                "b.rs".to_string(),
+                vec![crumb(1, "TODO: four"), crumb(2, "five")],
+            ),
+        ]
+    }
+
+    /// run the prompt with the answers, the chosen crumbs if applying
+    fn answers(answers: &[&str]) -> Option<Vec<Vec<usize>>> {
+        let breads = breads();
+        let mut prompt = Prompt::new(&breads, "delete");
+        let mut answers = answers.iter();
+        let apply = prompt
+            .run(|_| {
+                answers
+                    .next()
+                    .map(|a| a.to_string())
+                    .ok_or("no more answers".to_string())
+            })
+            .unwrap();
+        assert!(answers.next().is_none(), "answers left");
+        apply.then(|| {
+            prompt
+                .chosen()
+                .into_iter()
+                .map(|c| {
+                    let mut c = c.into_iter().collect::<Vec<_>>();
+                    c.sort();
+                    c
+                })
+                .collect()
+        })
+    }
+
+    #[test]
+    fn test_prompt_all() {
+        // unknown answer and help ask again
+        assert_eq!(
+            answers(&["what", "?", "s", "y"]),
+            Some(vec![vec![0, 1, 2], vec![0, 1]])
+        );
+        assert_eq!(answers(&["n"]), None);
+    }
+
+    #[test]
+    fn test_prompt_bread_by_bread() {
+        assert_eq!(
+            answers(&["i", "n", "y", "y"]),
+            Some(vec![vec![], vec![0, 1]])
+        );
+        // skip the file, then go back and choose it
+        assert_eq!(
+            answers(&["i", "n", "b", "y", "n", "y"]),
+            Some(vec![vec![0, 1, 2], vec![]])
+        );
+        assert_eq!(
+            answers(&["i", "a", "y"]),
+            Some(vec![vec![0, 1, 2], vec![0, 1]])
+        );
+        // quit and apply what is chosen
+        assert_eq!(
+            answers(&["i", "y", "q", "y"]),
+            Some(vec![vec![0, 1, 2], vec![]])
+        );
+        assert_eq!(answers(&["i", "y", "x"]), None);
+        // back from confirm
+        assert_eq!(
+            answers(&["i", "y", "y", "b", "n", "y"]),
+            Some(vec![vec![0, 1, 2], vec![]])
+        );
+        // nothing chosen
+        assert_eq!(answers(&["i", "n", "n"]), None);
+    }
+
+    #[test]
+    fn test_prompt_crumb_by_crumb() {
+        assert_eq!(
+            answers(&["i", "i", "y", "n", "y", "i", "n", "y", "y"]),
+            Some(vec![vec![0, 2], vec![1]])
+        );
+        // back undoes the choice
+        assert_eq!(
+            answers(&["i", "i", "y", "b", "n", "a", "i", "f", "y"]),
+            Some(vec![vec![1, 2], vec![]])
+        );
+        // TODO in the other bread is chosen too and not asked again
+        assert_eq!(
+            answers(&["i", "i", "Y", "n", "i", "n", "y"]),
+            Some(vec![vec![0, 2], vec![0]])
+        );
+        assert_eq!(
+            answers(&["i", "i", "N", "b", "y", "a", "y", "y"]),
+            Some(vec![vec![0, 1, 2], vec![0, 1]])
+        );
+        // keyword wins over the bread choice
+        assert_eq!(
+            answers(&["i", "i", "N", "y", "y", "y"]),
+            Some(vec![vec![1], vec![1]])
+        );
+        // the crumb without keyword
+        assert_eq!(
+            answers(&["i", "n", "i", "n", "Y", "y", "y"]),
+            Some(vec![vec![], vec![1]])
+        );
+    }
+}
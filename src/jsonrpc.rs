@@ -0,0 +1,23 @@
+//! The json-rpc 2.0 messages shared by the language server and the mcp server
+
+use serde_json::{Value, json};
+
+/// the json-rpc error codes
+pub(crate) const METHOD_NOT_FOUND: i64 = -32601;
+pub(crate) const INVALID_PARAMS: i64 = -32602;
+
+/// the result of request, or the error code and message
+pub(crate) type RpcResult = Result<Value, (i64, String)>;
+
+pub(crate) fn response(id: &Value, result: RpcResult) -> Value {
+    match result {
+        Ok(result) => json!({"jsonrpc": "2.0", "id": id, "result": result}),
+        Err((code, message)) => {
+            json!({"jsonrpc": "2.0", "id": id, "error": {"code": code, "message": message}})
+        }
+    }
+}
+
+pub(crate) fn notification(method: &str, params: Value) -> Value {
+    json!({"jsonrpc": "2.0", "method": method, "params": params})
+}
@@ -1,100 +1,232 @@
 #![feature(never_type)]
 #![feature(exit_status_error)]
 
+use std::cell::Cell;
 use std::collections::HashSet;
+use std::io::{self, IsTerminal};
 
 use datatypes::Bread;
 
+mod apply;
 pub mod args;
+pub mod blame;
+mod cache;
 pub mod config;
 pub mod datatypes;
 pub mod fs_operation;
-
-use datatypes::*;
+mod interact;
+mod jsonrpc;
+pub mod lsp;
+pub mod mcp;
+mod notebook;
+pub mod output;
+pub mod template;
+pub mod tui;
+pub mod watch;
 
 pub fn prompt(mut conf: config::Config) -> Result<Option<HashSet<String>>, String> {
-    if conf.delete {
-        // only delete is true gonna triger the prompt
+    if let Some(args::Command::Lsp) = conf.command {
+        lsp::serve(conf)?;
+        Ok(None)
+    } else if let Some(args::Command::Mcp) = conf.command {
+        mcp::serve(conf)?;
+        Ok(None)
+    } else if let Some(args::Command::Adopt { .. }) = conf.command {
+        let breads = fs_operation::handle_files(conf.clone())
+            .filter_map(|b| conf.selection.select(b))
+            .collect::<Vec<_>>();
+        if conf.yes || conf.no_input {
+            return change_without_prompt(breads, "adopt", &conf);
+        }
         let mut rl = rustyline::Editor::<()>::new();
+        interact::prompt_breads(breads, "adopt", |q| {
+            rl.readline(q)
+                .map_err(|e| format!("error in prompt readline {}", e))
+        })
+    } else if let Some(args::Command::Add {
+        location,
+        text,
+        append,
+    }) = &conf.command
+    {
+        let (path, line) = datatypes::parse_crumb_id(location).ok_or(format!(
+            "invalid location {}, should be path:line",
+            location
+        ))?;
+        let file_path = fs_operation::add_crumb(std::path::Path::new(path), line, text, *append)
+            .map_err(|e| e.to_string())?;
+        println!("added the crumb at {}:{}", file_path, line);
+        Ok(Some(HashSet::from([file_path])))
+    } else if let Some(args::Command::Apply { file }) = &conf.command {
+        apply::run(file.as_deref(), conf.no_input && !conf.yes, &conf)
+    } else if conf.tui {
+        tui::run(conf)
+    } else if conf.delete {
+        // only delete is true gonna triger the prompt
         conf.delete = false;
-        let breads = fs_operation::handle_files(conf).collect::<Vec<_>>();
-        let mut files_changed = None;
-        loop {
-            breads.iter().for_each(|b| println!("{}", b));
-            match rl.readline("Are you sure you want to delete all crumbs? (y/n/s/i): ") {
-                Ok(s) => match s.as_str() {
-                    "y" => {
-                        let mut cache = HashSet::new();
-                        for b in breads {
-                            cache.insert(
-                                fs_operation::delete_the_crumbs(b).map_err(|e| e.to_string())?,
-                            );
-                        }
-                        if !cache.is_empty() {
-                            files_changed = Some(cache)
-                        };
-                    }
-                    "n" => (), // do nothing
-                    "s" => continue,
-                    "i" => {
-                        files_changed = Some(prompt_bread(breads.into_iter(), &mut rl, "delete")?)
-                    }
-                    _ => return Err("I don't understand, please give y/n/s/i".to_string()),
-                },
-                Err(e) => return Err(format!("error in prompt readline {}", e.to_string())),
-            }
-            break;
+        let breads = fs_operation::handle_files(conf.clone())
+            .filter_map(|b| conf.selection.select(b))
+            .collect::<Vec<_>>();
+        if conf.yes || conf.no_input {
+            return change_without_prompt(breads, "delete", &conf);
         }
-        Ok(files_changed)
+        let mut rl = rustyline::Editor::<()>::new();
+        interact::prompt_breads(breads, "delete", |q| {
+            rl.readline(q)
+                .map_err(|e| format!("error in prompt readline {}", e))
+        })
     } else if conf.restore {
+        let breads = fs_operation::handle_files(conf.clone())
+            .filter_map(|b| conf.selection.select(b))
+            .collect::<Vec<_>>();
+        if conf.yes || conf.no_input {
+            return change_without_prompt(breads, "restore", &conf);
+        }
         let mut rl = rustyline::Editor::<()>::new();
-        let breads = fs_operation::handle_files(conf).collect::<Vec<_>>();
-        let mut files_changed = None;
-        loop {
-            breads.iter().for_each(|b| println!("{}", b));
-            match rl.readline("Are you sure you want to restore all crumbs? (y/n/s/i): ") {
-                Ok(s) => match s.as_str() {
-                    "y" => {
-                        let mut cache = HashSet::new();
-                        for b in breads {
-                            cache.insert(
-                                fs_operation::restore_the_crumb(b).map_err(|e| e.to_string())?,
-                            );
-                        }
-                        if !cache.is_empty() {
-                            files_changed = Some(cache)
-                        };
-                    }
-                    "n" => (), // do nothing
-                    "s" => continue,
-                    "i" => {
-                        files_changed = Some(prompt_bread(breads.into_iter(), &mut rl, "restore")?)
-                    }
-                    _ => return Err("I don't understand, please give y/n/s/i".to_string()),
-                },
-                Err(e) => return Err(format!("error in prompt readline {}", e.to_string())),
-            }
-            break;
+        interact::prompt_breads(breads, "restore", |q| {
+            rl.readline(q)
+                .map_err(|e| format!("error in prompt readline {}", e))
+        })
+    } else if conf.watch {
+        watch::watch(conf)?
+    } else if conf.stats {
+        let json = matches!(conf.output, config::OutputFormat::Json);
+        let stats = output::Stats::new(&fs_operation::handle_files(conf).collect::<Vec<_>>());
+        if json {
+            println!(
+                "{}",
+                serde_json::to_string(&stats).map_err(|e| e.to_string())?
+            )
+        } else {
+            print!("{}", stats)
         }
-        Ok(files_changed)
+        Ok(None)
     } else {
+        // count the crumbs failing the check while scanning
+        let fail_on = conf.fail_on;
+        let severities = &conf.severities.clone();
+        let failed = &Cell::new(0);
+        let check = |b: &Bread| {
+            if let Some(level) = fail_on {
+                failed.set(
+                    failed.get()
+                        + b.crumbs
+                            .iter()
+                            .filter(|c| {
+                                !c.is_ignore() && severities.get(c.keyword.as_deref()) >= level
+                            })
+                            .count(),
+                )
+            }
+        };
+        let scan = |conf| fs_operation::handle_files(conf).inspect(check);
+
         match conf.output {
             config::OutputFormat::None => {
-                fs_operation::handle_files(conf).for_each(|b| println!("{}", b))
+                // paint the keywords only for terminal
+                let keywords = conf.keywords.clone();
+                let color = io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none();
+                scan(conf).for_each(|b| {
+                    if color {
+                        println!("{}", b.to_painted(|k| keywords.paint(k)))
+                    } else {
+                        println!("{}", b)
+                    }
+                })
             }
             config::OutputFormat::Json => {
                 println!(
                     "{}",
-                    serde_json::to_string(&fs_operation::handle_files(conf).collect::<Vec<_>>())
-                        .map_err(|e| e.to_string())?
+                    serde_json::to_string(&output::JsonDocument::new(
+                        &scan(conf).collect::<Vec<_>>()
+                    ))
+                    .map_err(|e| e.to_string())?
                 )
             }
-            config::OutputFormat::List => fs_operation::handle_files(conf).for_each(|b| {
+            config::OutputFormat::Jsonl => {
+                // sorting needs all breads, otherwise stream them as scanned
+                let breads: Box<dyn Iterator<Item = Bread>> = if conf.sort.is_some() {
+                    Box::new(scan(conf))
+                } else {
+                    Box::new(fs_operation::stream_files(conf).inspect(check))
+                };
+                for b in breads {
+                    for record in output::to_jsonl_records(&b) {
+                        println!(
+                            "{}",
+                            serde_json::to_string(&record).map_err(|e| e.to_string())?
+                        )
+                    }
+                }
+            }
+            config::OutputFormat::List => scan(conf).for_each(|b| {
                 b.crumbs
                     .iter()
                     .for_each(|crumb| println!("{}:{}", b.file_path, crumb.list_format()))
             }),
-            config::OutputFormat::Range => fs_operation::handle_files(conf).for_each(|b| {
+            config::OutputFormat::Org => scan(conf).for_each(|b| {
+                let Ok(org) = b.to_org();
+                print!("{}", org)
+            }),
+            config::OutputFormat::Markdown => {
+                let group_by = conf.group_by.clone();
+                let keywords = conf.keywords.clone();
+                print!(
+                    "{}",
+                    output::to_markdown(&scan(conf).collect::<Vec<_>>(), &group_by, &keywords)
+                )
+            }
+            config::OutputFormat::Sarif => {
+                let severities = conf.severities.clone();
+                let keywords = conf.keywords.clone();
+                let breads = scan(conf).collect::<Vec<_>>();
+                println!(
+                    "{}",
+                    serde_json::to_string(&output::to_sarif(&breads, &severities, &keywords))
+                        .map_err(|e| e.to_string())?
+                )
+            }
+            config::OutputFormat::Html => {
+                print!("{}", output::to_html(&scan(conf).collect::<Vec<_>>())?)
+            }
+            config::OutputFormat::Csv | config::OutputFormat::Tsv => {
+                let delimiter = match conf.output {
+                    config::OutputFormat::Csv => output::Delimiter::Comma,
+                    _ => output::Delimiter::Tab,
+                };
+                let columns = conf.columns.clone();
+                println!("{}", output::delimited_header(&columns, delimiter));
+                scan(conf).for_each(|b| {
+                    output::delimited_rows(&b, &columns, delimiter).for_each(|row| println!("{}", row))
+                })
+            }
+            config::OutputFormat::Template(ref template) => {
+                let template = template.clone();
+                let severities = conf.severities.clone();
+                print!("{}", template.render_header());
+                scan(conf).for_each(|b| print!("{}", template.render_bread(&b, &severities)));
+                print!("{}", template.render_footer());
+            }
+            config::OutputFormat::Vimgrep => scan(conf).for_each(|b| {
+                b.crumbs
+                    .iter()
+                    .for_each(|crumb| println!("{}:{}", b.file_path, crumb.vimgrep_format()))
+            }),
+            config::OutputFormat::Errorformat => {
+                let severities = conf.severities.clone();
+                scan(conf).for_each(|b| {
+                    b.crumbs.iter().for_each(|crumb| {
+                        println!(
+                            "{}:{}",
+                            b.file_path,
+                            crumb.errorformat_format(
+                                severities.get(crumb.keyword.as_deref()).as_str()
+                            )
+                        )
+                    })
+                })
+            }
+            config::OutputFormat::Range => scan(conf).for_each(|b| {
                 b.crumbs.iter().for_each(|crumb| {
                     println!(
                         r#"File path: {}
@@ -113,109 +245,48 @@ Context around:
                 })
             }),
         }
+
+        if let Some(level) = fail_on
+            && failed.get() > 0
+        {
+            return Err(format!(
+                "{} crumbs at or above {} severity",
+                failed.get(),
+                level.as_str()
+            ));
+        }
         Ok(None)
     }
 }
 
-fn prompt_bread(
-    breads: impl Iterator<Item = Bread>,
-    rl: &mut rustyline::Editor<()>,
+/// delete, restore or adopt all crumbs of breads without prompt. Only print what
+/// would change if `--no-input` is given without `--yes`
+fn change_without_prompt(
+    breads: Vec<Bread>,
     op: &str,
-) -> Result<HashSet<String>, String> {
+    conf: &config::Config,
+) -> Result<Option<HashSet<String>>, String> {
+    let summary = output::ChangeSummary::new(op, conf.yes, &breads);
     let mut files_changed = HashSet::new();
-    for b in breads {
-        loop {
-            // incase need show again
-            println!("{}", b);
-            match rl.readline(&format!(
-                "Are you sure you want to {} this bread {}? (y/n/s/i): ",
-                op, b.file_path
-            )) {
-                Ok(s) => match s.as_str() {
-                    "y" => match op {
-                        "delete" => {
-                            files_changed.insert(
-                                fs_operation::delete_the_crumbs(b).map_err(|e| e.to_string())?,
-                            );
-                        }
-                        "restore" => {
-                            files_changed.insert(
-                                fs_operation::restore_the_crumb(b).map_err(|e| e.to_string())?,
-                            );
-                        }
-                        _ => (),
-                    },
-                    "n" => {}
-                    "s" => {
-                        continue;
-                    }
-                    "i" => {
-                        let go_to_handle = prompt_crumbs(b.crumbs.iter(), rl, op)?;
-                        if go_to_handle.len() != 0 {
-                            match op {
-                                "delete" => {
-                                    files_changed.insert(
-                                        fs_operation::delete_the_crumbs_on_special_index(
-                                            b,
-                                            go_to_handle,
-                                        )
-                                        .map_err(|e| e.to_string())?,
-                                    );
-                                }
-                                "restore" => {
-                                    files_changed.insert(
-                                        fs_operation::restore_the_crumb_on_special_index(
-                                            b,
-                                            go_to_handle,
-                                        )
-                                        .map_err(|e| e.to_string())?,
-                                    );
-                                }
-                                _ => (),
-                            }
-                        }
-                    }
-                    _ => {
-                        println!("I don't understand, please give y/n/s/i");
-                    }
-                },
-                Err(e) => return Err(e.to_string()),
-            }
-            break;
+    if conf.yes {
+        for b in breads {
+            let indexes = (0..b.crumbs.len()).collect();
+            let file_path = match op {
+                "delete" => fs_operation::delete_crumbs_on_indexes(b, &indexes),
+                "adopt" => fs_operation::adopt_crumbs_on_indexes(b, &indexes),
+                _ => fs_operation::restore_crumbs_on_indexes(b, &indexes),
+            };
+            files_changed.insert(file_path.map_err(|e| e.to_string())?);
         }
     }
-    Ok(files_changed)
-}
 
-fn prompt_crumbs<'a>(
-    crumbs: impl Iterator<Item = &'a Crumb>,
-    rl: &mut rustyline::Editor<()>,
-    op: &str,
-) -> Result<HashSet<usize>, String> {
-    let mut going_to_handle_crumbs_indexes = HashSet::new();
-    for (ind, c) in crumbs.enumerate() {
-        loop {
-            println!("{}", c);
-            match rl.readline(&format!(
-                "Are you sure you want to {} this crumb? (y/n/s): ",
-                op
-            )) {
-                Ok(s) => match s.as_str() {
-                    "y" => {
-                        going_to_handle_crumbs_indexes.insert(ind);
-                    }
-                    "n" => {}
-                    "s" => {
-                        continue;
-                    }
-                    _ => {
-                        println!("I don't understand, please give y/n/s");
-                    }
-                },
-                Err(e) => return Err(e.to_string()),
-            }
-            break;
-        }
+    if matches!(conf.output, config::OutputFormat::Json) {
+        println!(
+            "{}",
+            serde_json::to_string(&summary).map_err(|e| e.to_string())?
+        )
+    } else {
+        print!("{}", summary)
     }
-    Ok(going_to_handle_crumbs_indexes)
+    Ok(Some(files_changed).filter(|f| !f.is_empty()))
 }
@@ -0,0 +1,501 @@
+//! The language server of crumbs, speaking LSP over stdio
+//!
+//! Crumbs are published as diagnostics of the opened documents, and they can be
+//! deleted or restored by the code actions as text edits.
+
+use super::config::{Config, SeverityTable};
+use super::datatypes::*;
+use super::fs_operation::{self, delete_crumb_in_line, restore_crumb_in_line};
+use super::jsonrpc::{self, INVALID_PARAMS, METHOD_NOT_FOUND, RpcResult};
+use super::notebook;
+use super::output::JsonDocument;
+use log::debug;
+use regex::{Regex, RegexBuilder};
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+/// the command of workspace/executeCommand listing all crumbs of workspace,
+/// the result is the json output document
+pub const LIST_CRUMBS_COMMAND: &str = "codeitlater.listCrumbs";
+
+/// the SymbolKind of MARK crumbs in workspace symbols, MARK marks the
+/// section of code, Namespace is the closest one
+const MARK_SYMBOL_KIND: u8 = 3;
+
+static MARK_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    RegexBuilder::new(r"^\s*(MARK):\s*(.*)")
+        .case_insensitive(true)
+        .build()
+        .unwrap()
+});
+
+/// read one message with the Content-Length header. None if the input closed
+fn read_message(input: &mut impl BufRead) -> io::Result<Option<Value>> {
+    let mut length = None;
+    loop {
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            if length.is_some() {
+                break;
+            }
+            continue;
+        }
+        if let Some((k, v)) = line.split_once(':')
+            && k.eq_ignore_ascii_case("content-length")
+        {
+            length = v.trim().parse::<usize>().ok()
+        }
+    }
+
+    let mut body = vec![0; length.unwrap()];
+    input.read_exact(&mut body)?;
+    match serde_json::from_slice(&body) {
+        Ok(msg) => Ok(Some(msg)),
+        Err(e) => {
+            debug!("cannot parse message: {}", e);
+            Ok(Some(Value::Null))
+        }
+    }
+}
+
+fn write_message(output: &mut impl Write, msg: &Value) -> io::Result<()> {
+    let body = msg.to_string();
+    write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    output.flush()
+}
+
+/// the path of `file://` uri
+pub(crate) fn uri_to_path(uri: &str) -> Option<PathBuf> {
+    let encoded = uri.strip_prefix("file://")?.as_bytes();
+    let mut path = Vec::with_capacity(encoded.len());
+    let mut i = 0;
+    while i < encoded.len() {
+        if encoded[i] == b'%'
+            && let Some(b) = encoded
+                .get(i + 1..i + 3)
+                .and_then(|hex| std::str::from_utf8(hex).ok())
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+        {
+            path.push(b);
+            i += 3;
+        } else {
+            path.push(encoded[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(path).ok().map(PathBuf::from)
+}
+
+/// the `file://` uri of path, relative path is based on current dir
+pub(crate) fn path_to_uri(path: &Path) -> String {
+    let path = std::path::absolute(path).unwrap_or(path.to_path_buf());
+    let mut uri = String::from("file://");
+    for b in path.to_string_lossy().bytes() {
+        if b.is_ascii_alphanumeric() || b"-._~/".contains(&b) {
+            uri.push(b as char)
+        } else {
+            uri.push_str(&format!("%{:02X}", b))
+        }
+    }
+    uri
+}
+
+/// lines of document without the line endings
+fn lines_of(text: &str) -> Vec<&str> {
+    text.split('\n')
+        .map(|l| l.strip_suffix('\r').unwrap_or(l))
+        .collect()
+}
+
+/// LSP position (and SARIF column) counts the utf-16 code units by default
+pub(crate) fn utf16_len(s: &str) -> usize {
+    s.encode_utf16().count()
+}
+
+fn position(line: usize, character: usize) -> Value {
+    json!({"line": line, "character": character})
+}
+
+/// the range from the crumb start to the end of its last tail line
+fn crumb_range(crumb: &Crumb, lines: &[&str]) -> Value {
+    let line = lines.get(crumb.line_num - 1).copied().unwrap_or_default();
+    let start = utf16_len(line.get(..crumb.position).unwrap_or(line));
+    let last = *crumb.all_lines_num().last().unwrap();
+    let end = lines.get(last - 1).map(|l| utf16_len(l)).unwrap_or(0);
+    json!({"start": position(crumb.line_num - 1, start), "end": position(last - 1, end)})
+}
+
+fn diagnostic(crumb: &Crumb, lines: &[&str], severities: &SeverityTable) -> Value {
+    let mut diag = json!({
+        "range": crumb_range(crumb, lines),
+        "severity": severities.get(crumb.keyword.as_deref()).lsp_severity(),
+        "source": "codeitlater",
+        "message": crumb.view_content,
+    });
+    if let Some(k) = &crumb.keyword {
+        diag["code"] = json!(k)
+    }
+    diag
+}
+
+/// the edit replacing the line `line_num` (1-based) with new line,
+/// or removing the whole line if new line is None
+fn line_edit(lines: &[&str], line_num: usize, new_line: Option<String>) -> Value {
+    let line = lines.get(line_num - 1).copied().unwrap_or_default();
+    match new_line {
+        Some(new_line) => json!({
+            "range": {"start": position(line_num - 1, 0), "end": position(line_num - 1, utf16_len(line))},
+            "newText": new_line,
+        }),
+        // the last line doesn't have the line ending to remove
+        None if line_num >= lines.len() => json!({
+            "range": {"start": position(line_num - 1, 0), "end": position(line_num - 1, utf16_len(line))},
+            "newText": "",
+        }),
+        None => json!({
+            "range": {"start": position(line_num - 1, 0), "end": position(line_num, 0)},
+            "newText": "",
+        }),
+    }
+}
+
+/// the text edits deleting this crumb, same as `delete_the_crumbs_on_special_index`
+fn delete_edits(crumb: &Crumb, lines: &[&str]) -> Vec<Value> {
+    crumb
+        .all_lines_num_postion_pair()
+        .into_iter()
+        .map(|(line_num, pos)| {
+            let line = lines.get(line_num - 1).copied().unwrap_or_default();
+            line_edit(lines, line_num, delete_crumb_in_line(line, pos))
+        })
+        .collect()
+}
+
+/// the text edits restoring this crumb to normal comment, same as
+/// `restore_the_crumb_on_special_index`
+fn restore_edits(crumb: &Crumb, lines: &[&str]) -> Vec<Value> {
+    crumb
+        .all_lines_num_postion_and_header_content()
+        .into_iter()
+        .map(|(line_num, pos, header, content)| {
+            let line = lines.get(line_num - 1).copied().unwrap_or_default();
+            line_edit(
+                lines,
+                line_num,
+                Some(restore_crumb_in_line(line, pos, header, content)),
+            )
+        })
+        .collect()
+}
+
+struct Server {
+    conf: Config,
+    /// the root of workspace, scan the targets of config if it is None
+    root: Option<PathBuf>,
+    /// the content of opened documents by their uri
+    documents: HashMap<String, String>,
+}
+
+impl Server {
+    /// the path and content of document, read the file if it isn't opened
+    fn document(&self, uri: &str) -> Option<(PathBuf, String)> {
+        let path = uri_to_path(uri)?;
+        let text = match self.documents.get(uri) {
+            Some(t) => t.clone(),
+            None => fs::read_to_string(&path).ok()?,
+        };
+        Some((path, text))
+    }
+
+    fn bread_of(path: &Path, text: &str, conf: &Config) -> Option<Bread> {
+        // the crumbs of notebook are located in cells, not in the document
+        if notebook::is_notebook(path) {
+            return None;
+        }
+        fs_operation::bake_content(path, text.as_bytes(), conf)
+    }
+
+    fn diagnostics(&self, uri: &str) -> Value {
+        let Some((path, text)) = self.document(uri) else {
+            return json!([]);
+        };
+        let lines = lines_of(&text);
+        let diags = Self::bread_of(&path, &text, &self.conf)
+            .map(|b| {
+                b.crumbs
+                    .iter()
+                    .map(|c| diagnostic(c, &lines, &self.conf.severities))
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        json!({"uri": uri, "diagnostics": diags})
+    }
+
+    /// all breads of workspace, the opened documents use their unsaved content
+    fn workspace_breads(&self) -> Vec<Bread> {
+        let mut conf = self.conf.clone();
+        conf.delete = false;
+        conf.restore = false;
+        if let Some(root) = &self.root {
+            conf.files = vec![root.to_string_lossy().to_string()]
+        }
+
+        let opened = self
+            .documents
+            .keys()
+            .filter_map(|uri| uri_to_path(uri))
+            .collect::<Vec<_>>();
+        let mut breads = fs_operation::handle_files(conf)
+            .filter(|b| {
+                let p = std::path::absolute(&b.file_path).unwrap_or_default();
+                !opened.contains(&p)
+            })
+            .collect::<Vec<_>>();
+        for (uri, text) in &self.documents {
+            if let Some(path) = uri_to_path(uri)
+                && let Some(b) = Self::bread_of(&path, text, &self.conf)
+            {
+                breads.push(b)
+            }
+        }
+        breads
+    }
+
+    fn initialize(&mut self, params: &Value) -> RpcResult {
+        self.root = params["rootUri"]
+            .as_str()
+            .or(params["workspaceFolders"][0]["uri"].as_str())
+            .and_then(uri_to_path)
+            .or(params["rootPath"].as_str().map(PathBuf::from));
+
+        Ok(json!({
+            "capabilities": {
+                "textDocumentSync": {"openClose": true, "change": 1, "save": {"includeText": true}},
+                "codeActionProvider": {"codeActionKinds": ["quickfix", "refactor.rewrite"]},
+                "workspaceSymbolProvider": true,
+                "executeCommandProvider": {"commands": [LIST_CRUMBS_COMMAND]},
+            },
+            "serverInfo": {"name": "codeitlater", "version": env!("CARGO_PKG_VERSION")},
+        }))
+    }
+
+    fn code_actions(&self, params: &Value) -> RpcResult {
+        let uri = params["textDocument"]["uri"]
+            .as_str()
+            .ok_or((INVALID_PARAMS, "textDocument uri is missing".to_string()))?;
+        let (first, last) = (
+            params["range"]["start"]["line"].as_u64().unwrap_or(0) as usize,
+            params["range"]["end"]["line"].as_u64().unwrap_or(0) as usize,
+        );
+        let Some((path, text)) = self.document(uri) else {
+            return Ok(json!([]));
+        };
+        let lines = lines_of(&text);
+        let Some(bread) = Self::bread_of(&path, &text, &self.conf) else {
+            return Ok(json!([]));
+        };
+
+        let mut actions = vec![];
+        for crumb in bread.crumbs.iter().filter(|c| {
+            c.all_lines_num()
+                .iter()
+                .any(|l| (first..=last).contains(&(l - 1)))
+        }) {
+            let diag = diagnostic(crumb, &lines, &self.conf.severities);
+            actions.push(json!({
+                "title": format!("Delete crumb: {}", crumb.view_content),
+                "kind": "quickfix",
+                "diagnostics": [diag],
+                "edit": {"changes": {uri: delete_edits(crumb, &lines)}},
+            }));
+            actions.push(json!({
+                "title": format!("Restore crumb to normal comment: {}", crumb.view_content),
+                "kind": "refactor.rewrite",
+                "diagnostics": [diag],
+                "edit": {"changes": {uri: restore_edits(crumb, &lines)}},
+            }));
+        }
+        Ok(Value::Array(actions))
+    }
+
+    /// MARK crumbs as workspace symbols
+    fn workspace_symbols(&self, params: &Value) -> RpcResult {
+        let query = params["query"].as_str().unwrap_or("").to_lowercase();
+        let mut symbols = vec![];
+        for bread in self.workspace_breads() {
+            let uri = path_to_uri(Path::new(&bread.file_path));
+            for crumb in &bread.crumbs {
+                let mut mark = crumb.clone();
+                if !mark.filter_keywords(&MARK_REGEX)
+                    || !mark.view_content.to_lowercase().contains(&query)
+                {
+                    continue;
+                }
+                symbols.push(json!({
+                    "name": mark.view_content,
+                    "kind": MARK_SYMBOL_KIND,
+                    "location": {
+                        "uri": uri,
+                        "range": {"start": position(crumb.line_num - 1, 0), "end": position(crumb.line_num, 0)},
+                    },
+                    "containerName": bread.file_path,
+                }))
+            }
+        }
+        Ok(Value::Array(symbols))
+    }
+
+    fn execute_command(&self, params: &Value) -> RpcResult {
+        match params["command"].as_str() {
+            Some(LIST_CRUMBS_COMMAND) => {
+                serde_json::to_value(JsonDocument::new(&self.workspace_breads()))
+                    .map_err(|e| (INVALID_PARAMS, e.to_string()))
+            }
+            c => Err((INVALID_PARAMS, format!("unknown command {:?}", c))),
+        }
+    }
+
+    fn request(&mut self, method: &str, params: &Value) -> RpcResult {
+        match method {
+            "initialize" => self.initialize(params),
+            "shutdown" => Ok(Value::Null),
+            "textDocument/codeAction" => self.code_actions(params),
+            "workspace/symbol" => self.workspace_symbols(params),
+            "workspace/executeCommand" => self.execute_command(params),
+            _ => Err((METHOD_NOT_FOUND, format!("unknown method {}", method))),
+        }
+    }
+
+    /// handle the notification, return the diagnostics should be published
+    fn notify(&mut self, method: &str, params: &Value) -> Option<Value> {
+        let uri = params["textDocument"]["uri"].as_str()?.to_string();
+        match method {
+            "textDocument/didOpen" => {
+                let text = params["textDocument"]["text"].as_str()?;
+                self.documents.insert(uri.clone(), text.to_string());
+            }
+            "textDocument/didChange" => {
+                // full sync, the last change is the whole document
+                let changes = params["contentChanges"].as_array()?;
+                let text = changes.last()?["text"].as_str()?;
+                self.documents.insert(uri.clone(), text.to_string());
+            }
+            "textDocument/didSave" => {
+                if let Some(text) = params["text"].as_str() {
+                    self.documents.insert(uri.clone(), text.to_string());
+                }
+            }
+            "textDocument/didClose" => {
+                self.documents.remove(&uri);
+                return Some(json!({"uri": uri, "diagnostics": []}));
+            }
+            _ => return None,
+        }
+        Some(self.diagnostics(&uri))
+    }
+}
+
+/// run the language server on the input and output until the `exit` notification
+pub fn run(conf: Config, mut input: impl BufRead, mut output: impl Write) -> Result<(), String> {
+    let mut server = Server {
+        conf,
+        root: None,
+        documents: HashMap::new(),
+    };
+
+    while let Some(msg) = read_message(&mut input).map_err(|e| e.to_string())? {
+        let Some(method) = msg["method"].as_str() else {
+            // responses of client or broken message
+            continue;
+        };
+        let params = &msg["params"];
+
+        let reply = match (method, msg.get("id")) {
+            ("exit", _) => return Ok(()),
+            (_, Some(id)) => jsonrpc::response(id, server.request(method, params)),
+            (_, None) => match server.notify(method, params) {
+                Some(diags) => jsonrpc::notification("textDocument/publishDiagnostics", diags),
+                None => continue,
+            },
+        };
+        write_message(&mut output, &reply).map_err(|e| e.to_string())?
+    }
+
+    Ok(())
+}
+
+/// run the language server over stdio
+pub fn serve(conf: Config) -> Result<(), String> {
+    run(conf, io::stdin().lock(), io::stdout().lock())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_uri_and_path() {
+        assert_eq!(
+            uri_to_path("file:///tmp/a%20b/c%23.rs"),
+            Some(PathBuf::from("/tmp/a b/c#.rs"))
+        );
+        assert_eq!(uri_to_path("untitled:Untitled-1"), None);
+        assert_eq!(
+            path_to_uri(Path::new("/tmp/a b/c#.rs")),
+            "file:///tmp/a%20b/c%23.rs"
+        );
+    }
+
+    #[test]
+    fn test_message_framing() {
+        let mut out = vec![];
+        write_message(&mut out, &json!({"id": 1})).unwrap();
+        assert_eq!(
+            String::from_utf8(out.clone()).unwrap(),
+            "Content-Length: 8\r\n\r\n{\"id\":1}"
+        );
+
+        out.extend_from_slice(
+            b"Content-Type: application/vscode-jsonrpc\r\nContent-Length: 2\r\n\r\n{}",
+        );
+        let mut input = out.as_slice();
+        assert_eq!(read_message(&mut input).unwrap(), Some(json!({"id": 1})));
+        assert_eq!(read_message(&mut input).unwrap(), Some(json!({})));
+        assert_eq!(read_message(&mut input).unwrap(), None);
+    }
+
+    #[test]
+    fn test_edits() {
+        let text = "fn a() {} //:= TODO: one...\n//:= two\nlet 字 = 1; //:= three";
+        let lines = lines_of(text);
+        let mut head = Crumb::new(1, 10, "TODO: one...".to_string(), "//".to_string());
+        head.add_tail(Crumb::new(2, 0, "two".to_string(), "//".to_string()));
+
+        assert_eq!(
+            delete_edits(&head, &lines),
+            vec![
+                json!({"range": {"start": position(0, 0), "end": position(0, 27)}, "newText": "fn a() {} "}),
+                json!({"range": {"start": position(1, 0), "end": position(2, 0)}, "newText": ""}),
+            ]
+        );
+        assert_eq!(
+            restore_edits(&head, &lines)[1],
+            json!({"range": {"start": position(1, 0), "end": position(1, 8)}, "newText": "// two"}),
+        );
+
+        // utf-16 position and the last line without line ending
+        let last = Crumb::new(3, 13, "three".to_string(), "//".to_string());
+        assert_eq!(
+            crumb_range(&last, &lines),
+            json!({"start": position(2, 11), "end": position(2, 21)})
+        );
+    }
+}
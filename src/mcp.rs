@@ -0,0 +1,313 @@
+//! The Model Context Protocol server over stdio, so the coding agents can
+//! list the crumbs, read their context, and resolve or restore them.
+//!
+//! The messages are json-rpc 2.0, one message per line.
+
+use super::config::Config;
+use super::datatypes::*;
+use super::fs_operation;
+use super::jsonrpc::{self, INVALID_PARAMS, METHOD_NOT_FOUND, RpcResult};
+use super::notebook;
+use super::output::to_jsonl_records;
+use log::debug;
+use serde_json::{Value, json};
+use std::collections::HashSet;
+use std::io::{self, BufRead, Write};
+use std::path::{Component, Path, PathBuf};
+
+/// the protocol versions this server supports, the first one is the latest
+const PROTOCOL_VERSIONS: [&str; 3] = ["2025-06-18", "2025-03-26", "2024-11-05"];
+
+/// the context lines before and after crumb in `get_crumb_context` by default
+const DEFAULT_CONTEXT_LINES: usize = 5;
+
+fn tools() -> Value {
+    let id_schema = json!({
+        "type": "string",
+        "description": "the crumb id `path:line` from list_crumbs",
+    });
+    json!([
+        {
+            "name": "list_crumbs",
+            "description": "List the crumbs (the comments marked with `:=`) in the project. \
+                            Every crumb has the id used by the other tools.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "keyword": {"type": "string", "description": "only the crumbs of this keyword, like TODO"},
+                    "path": {"type": "string", "description": "only the crumbs in the files whose path contains it"},
+                    "query": {"type": "string", "description": "only the crumbs whose content contains it, case insensitive"},
+                },
+            },
+        },
+        {
+            "name": "get_crumb_context",
+            "description": "Get the crumb and the source lines around it.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "id": id_schema,
+                    "lines": {
+                        "type": "integer",
+                        "minimum": 0,
+                        "description": format!("the lines before and after the crumb, default {}", DEFAULT_CONTEXT_LINES),
+                    },
+                },
+                "required": ["id"],
+            },
+        },
+        {
+            "name": "resolve_crumb",
+            "description": "Delete the crumb after it is done. The line numbers (and ids) of the \
+                            crumbs after it in the same file may change, list them again.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {"id": id_schema},
+                "required": ["id"],
+            },
+        },
+        {
+            "name": "restore_crumb",
+            "description": "Restore the crumb back to the normal comment, the comment is kept \
+                            but it isn't the crumb anymore.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {"id": id_schema},
+                "required": ["id"],
+            },
+        },
+    ])
+}
+
+/// the crumb record with its id, same as the jsonl output record
+fn crumb_records(bread: &Bread) -> impl Iterator<Item = Value> + '_ {
+    bread
+        .crumbs
+        .iter()
+        .zip(to_jsonl_records(bread))
+        .map(|(c, r)| {
+            let mut record = serde_json::to_value(r).unwrap_or_default();
+            record["id"] = json!(bread.crumb_id(c));
+            record
+        })
+}
+
+/// the absolute path without `.` and `..`
+fn lexical_absolute(path: &Path) -> Option<PathBuf> {
+    let mut result = PathBuf::new();
+    for c in std::path::absolute(path).ok()?.components() {
+        match c {
+            Component::ParentDir => {
+                result.pop();
+            }
+            Component::CurDir => (),
+            c => result.push(c),
+        }
+    }
+    Some(result)
+}
+
+/// the path is one of targets or inside the target dirs
+fn in_targets(path: &Path, conf: &Config) -> bool {
+    let Some(path) = lexical_absolute(path) else {
+        return false;
+    };
+    conf.files.iter().any(|t| {
+        lexical_absolute(Path::new(t))
+            .map(|t| path.starts_with(t))
+            .unwrap_or(false)
+    })
+}
+
+/// the bread of the crumb id and the index of crumb in it
+fn find_crumb(id: &str, conf: &Config) -> Result<(Bread, usize), String> {
+    let (path, line) = parse_crumb_id(id).ok_or(format!("invalid crumb id {}", id))?;
+    // the crumb id of notebook has the cell before line
+    let (path, cell) = match parse_crumb_id(path) {
+        Some((p, cell)) if notebook::is_notebook(p) => (p, Some(cell)),
+        _ => (path, None),
+    };
+    let path = Path::new(path);
+    if !in_targets(path, conf) {
+        return Err(format!("{} isn't in the scanned files", path.display()));
+    }
+
+    let bread = fs_operation::bake_file(path, conf).ok_or(format!("cannot find crumb {}", id))?;
+    let ind = bread
+        .crumbs
+        .iter()
+        .position(|c| c.line_num == line && c.cell == cell)
+        .ok_or(format!("cannot find crumb {}", id))?;
+    Ok((bread, ind))
+}
+
+fn list_crumbs(args: &Value, conf: &Config) -> Result<Value, String> {
+    let keyword = args["keyword"].as_str();
+    let path = args["path"].as_str();
+    let query = args["query"].as_str().map(|q| q.to_lowercase());
+
+    let mut crumbs = vec![];
+    for bread in fs_operation::handle_files(conf.clone()) {
+        if path.is_some_and(|p| !bread.file_path.contains(p)) {
+            continue;
+        }
+        for (c, record) in bread.crumbs.iter().zip(crumb_records(&bread)) {
+            if keyword.is_some_and(|k| {
+                !c.keyword_name().is_some_and(|ck| {
+                    conf.keywords
+                        .canonical(ck)
+                        .eq_ignore_ascii_case(conf.keywords.canonical(k))
+                })
+            }) {
+                continue;
+            }
+            if query
+                .as_ref()
+                .is_some_and(|q| !c.content.to_lowercase().contains(q))
+            {
+                continue;
+            }
+            crumbs.push(record)
+        }
+    }
+    Ok(json!({"crumbs": crumbs}))
+}
+
+fn get_crumb_context(args: &Value, conf: &Config) -> Result<Value, String> {
+    let id = args["id"].as_str().ok_or("id is required")?;
+    let lines = args["lines"]
+        .as_u64()
+        .map(|l| l as usize)
+        .unwrap_or(DEFAULT_CONTEXT_LINES);
+    let (bread, ind) = find_crumb(id, conf)?;
+    let crumb = &bread.crumbs[ind];
+
+    let content = notebook::read_source(&bread.file_path, crumb.cell).map_err(|e| e.to_string())?;
+    let first = crumb.line_num.saturating_sub(lines).max(1);
+    let last = crumb.all_lines_num().last().unwrap().saturating_add(lines);
+    let context = content
+        .lines()
+        .enumerate()
+        .map(|(i, l)| (i + 1, l))
+        .filter(|(n, _)| (first..=last).contains(n))
+        .map(|(n, l)| json!({"line_num": n, "text": l}))
+        .collect::<Vec<_>>();
+
+    Ok(json!({
+        "crumb": crumb_records(&bread).nth(ind),
+        "language": bread.language(),
+        "context": context,
+    }))
+}
+
+fn resolve_crumb(args: &Value, conf: &Config) -> Result<Value, String> {
+    let id = args["id"].as_str().ok_or("id is required")?;
+    let (bread, ind) = find_crumb(id, conf)?;
+    let file_path = fs_operation::delete_crumbs_on_indexes(bread, &HashSet::from([ind]))
+        .map_err(|e| e.to_string())?;
+    Ok(json!({"resolved": id, "file_path": file_path}))
+}
+
+fn restore_crumb(args: &Value, conf: &Config) -> Result<Value, String> {
+    let id = args["id"].as_str().ok_or("id is required")?;
+    let (bread, ind) = find_crumb(id, conf)?;
+    let file_path = fs_operation::restore_crumbs_on_indexes(bread, &HashSet::from([ind]))
+        .map_err(|e| e.to_string())?;
+    Ok(json!({"restored": id, "file_path": file_path}))
+}
+
+/// call the tool. The error of tool is the result with `isError`, so the
+/// agent can see it. Only unknown tool is the json-rpc error
+fn call_tool(params: &Value, conf: &Config) -> RpcResult {
+    let args = &params["arguments"];
+    let result = match params["name"].as_str() {
+        Some("list_crumbs") => list_crumbs(args, conf),
+        Some("get_crumb_context") => get_crumb_context(args, conf),
+        Some("resolve_crumb") => resolve_crumb(args, conf),
+        Some("restore_crumb") => restore_crumb(args, conf),
+        name => return Err((INVALID_PARAMS, format!("unknown tool {:?}", name))),
+    };
+
+    Ok(match result {
+        Ok(v) => json!({
+            "content": [{"type": "text", "text": v.to_string()}],
+            "structuredContent": v,
+            "isError": false,
+        }),
+        Err(e) => json!({
+            "content": [{"type": "text", "text": e}],
+            "isError": true,
+        }),
+    })
+}
+
+fn request(method: &str, params: &Value, conf: &Config) -> RpcResult {
+    match method {
+        "initialize" => {
+            let version = params["protocolVersion"]
+                .as_str()
+                .filter(|v| PROTOCOL_VERSIONS.contains(v))
+                .unwrap_or(PROTOCOL_VERSIONS[0]);
+            Ok(json!({
+                "protocolVersion": version,
+                "capabilities": {"tools": {"listChanged": false}},
+                "serverInfo": {"name": "codeitlater", "version": env!("CARGO_PKG_VERSION")},
+                "instructions": "Crumbs are the comments marked with `:=` left in the code \
+                                 as reminders, like `//:= TODO: handle the error`.",
+            }))
+        }
+        "ping" => Ok(json!({})),
+        "tools/list" => Ok(json!({"tools": tools()})),
+        "tools/call" => call_tool(params, conf),
+        _ => Err((METHOD_NOT_FOUND, format!("unknown method {}", method))),
+    }
+}
+
+/// run the mcp server on the input and output until the input closed
+pub fn run(conf: Config, input: impl BufRead, mut output: impl Write) -> Result<(), String> {
+    for line in input.lines() {
+        let line = line.map_err(|e| e.to_string())?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let msg: Value = match serde_json::from_str(&line) {
+            Ok(m) => m,
+            Err(e) => {
+                debug!("cannot parse message: {}", e);
+                continue;
+            }
+        };
+
+        // notifications and responses of client don't need reply
+        let (Some(method), Some(id)) = (msg["method"].as_str(), msg.get("id")) else {
+            continue;
+        };
+        let reply = jsonrpc::response(id, request(method, &msg["params"], &conf));
+        writeln!(output, "{}", reply)
+            .and_then(|_| output.flush())
+            .map_err(|e| e.to_string())?
+    }
+    Ok(())
+}
+
+/// run the mcp server over stdio
+pub fn serve(conf: Config) -> Result<(), String> {
+    run(conf, io::stdin().lock(), io::stdout().lock())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_in_targets() {
+        let conf = Config {
+            files: vec![".".to_string(), "/tmp/a.rs".to_string()],
+            ..Default::default()
+        };
+        assert!(in_targets(Path::new("src/lib.rs"), &conf));
+        assert!(in_targets(Path::new("/tmp/a.rs"), &conf));
+        assert!(!in_targets(Path::new("/tmp/b.rs"), &conf));
+        assert!(!in_targets(Path::new("src/../../b.rs"), &conf));
+    }
+}
@@ -0,0 +1,362 @@
+//! Jupyter notebook, the crumbs are in the sources of its cells
+//!
+//! Every code cell is scanned as the file of the kernel language, and every
+//! markdown cell as the markdown file. The crumbs are located by the cell
+//! and the line in that cell. Rewriting the cells only replaces their sources
+//! in the json document, the rest of it is kept as it is.
+
+use super::config::language_key;
+use serde::Deserialize;
+use serde_json::value::RawValue;
+use std::fs::{self, OpenOptions};
+use std::io::{self, prelude::*};
+use std::ops::Range;
+use std::path::Path;
+
+/// the file extension of notebook, also the key of the code cells' comment
+/// symbols in table if the kernel language isn't there
+pub(crate) const EXTENSION: &str = "ipynb";
+
+pub(crate) fn is_notebook(path: impl AsRef<Path>) -> bool {
+    path.as_ref().extension().is_some_and(|e| e == EXTENSION)
+}
+
+#[derive(Deserialize)]
+struct RawNotebook<'a> {
+    #[serde(borrow)]
+    cells: Vec<RawCell<'a>>,
+    #[serde(default)]
+    metadata: Metadata,
+}
+
+#[derive(Deserialize)]
+struct RawCell<'a> {
+    cell_type: String,
+    #[serde(borrow)]
+    source: &'a RawValue,
+}
+
+#[derive(Default, Deserialize)]
+struct Metadata {
+    kernelspec: Option<KernelSpec>,
+    language_info: Option<LanguageInfo>,
+}
+
+#[derive(Deserialize)]
+struct KernelSpec {
+    language: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct LanguageInfo {
+    name: Option<String>,
+    file_extension: Option<String>,
+}
+
+/// the type of cell, the raw cells are never scanned
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum CellKind {
+    Code,
+    Markdown,
+    Raw,
+}
+
+/// one cell of notebook
+#[derive(Debug)]
+pub(crate) struct Cell {
+    /// the index of cell in notebook, starts from 1
+    pub(crate) index: usize,
+    pub(crate) kind: CellKind,
+    pub(crate) source: String,
+    /// the range of the source value in document
+    span: Range<usize>,
+    /// the ranges and the strings of the elements if the source is an array
+    elements: Option<Vec<(Range<usize>, String)>>,
+}
+
+#[derive(Debug)]
+pub(crate) struct Notebook<'a> {
+    text: &'a str,
+    cells: Vec<Cell>,
+    /// the keys in languages table may be the code cells' language
+    languages: Vec<String>,
+}
+
+impl<'a> Notebook<'a> {
+    pub(crate) fn parse(text: &'a str) -> Result<Self, String> {
+        let raw: RawNotebook = serde_json::from_str(text).map_err(|e| e.to_string())?;
+        // the raw values are borrowed from text
+        let span_of = |v: &RawValue| {
+            let start = (v.get().as_ptr() as usize).wrapping_sub(text.as_ptr() as usize);
+            let span = start..start + v.get().len();
+            match text.get(span.clone()) {
+                Some(s) if s.as_ptr() == v.get().as_ptr() => Ok(span),
+                _ => Err("cannot locate the cell source".to_string()),
+            }
+        };
+
+        let mut cells = Vec::with_capacity(raw.cells.len());
+        for (ind, c) in raw.cells.iter().enumerate() {
+            let (source, elements) = if c.source.get().starts_with('[') {
+                let mut elements = vec![];
+                for e in serde_json::from_str::<Vec<&RawValue>>(c.source.get())
+                    .map_err(|e| e.to_string())?
+                {
+                    let line: String = serde_json::from_str(e.get()).map_err(|e| e.to_string())?;
+                    elements.push((span_of(e)?, line));
+                }
+                (
+                    elements.iter().map(|(_, l)| l.as_str()).collect(),
+                    Some(elements),
+                )
+            } else {
+                (
+                    serde_json::from_str(c.source.get()).map_err(|e| e.to_string())?,
+                    None,
+                )
+            };
+
+            cells.push(Cell {
+                index: ind + 1,
+                kind: match c.cell_type.as_str() {
+                    "code" => CellKind::Code,
+                    "markdown" => CellKind::Markdown,
+                    _ => CellKind::Raw,
+                },
+                source,
+                span: span_of(c.source)?,
+                elements,
+            })
+        }
+
+        let info = raw.metadata.language_info.as_ref();
+        let languages = [
+            info.and_then(|i| i.file_extension.as_ref())
+                .map(|e| e.trim_start_matches('.').to_string()),
+            info.and_then(|i| i.name.clone()),
+            raw.metadata.kernelspec.and_then(|k| k.language),
+        ]
+        .into_iter()
+        .flatten()
+        .map(|l| {
+            let l = l.to_lowercase();
+            match language_key(&l) {
+                Some(key) => key.to_string(),
+                None => l,
+            }
+        })
+        .collect();
+
+        Ok(Self {
+            text,
+            cells,
+            languages,
+        })
+    }
+
+    pub(crate) fn cells(&self) -> &[Cell] {
+        &self.cells
+    }
+
+    /// the keys in languages table may be the code cells' language, the
+    /// first one in table should be used
+    pub(crate) fn languages(&self) -> &[String] {
+        &self.languages
+    }
+
+    /// the new document after every line (without the newline) of cells
+    /// rewritten by `edit`, which gets the cell index, the line number in
+    /// cell and the line. The line is removed if `edit` gives None
+    pub(crate) fn edit_lines(
+        &self,
+        mut edit: impl FnMut(usize, usize, String) -> Option<String>,
+    ) -> String {
+        let mut new_text = String::with_capacity(self.text.len());
+        let mut last = 0;
+        for cell in &self.cells {
+            let Some(source) =
+                cell.edit_source(self.text, |line_num, line| edit(cell.index, line_num, line))
+            else {
+                continue;
+            };
+            new_text.push_str(&self.text[last..cell.span.start]);
+            new_text.push_str(&source);
+            last = cell.span.end;
+        }
+        new_text.push_str(&self.text[last..]);
+        new_text
+    }
+}
+
+impl Cell {
+    /// the new json value of the source edited, None if nothing changed.
+    /// The elements not changed and the spaces between them are kept
+    fn edit_source(
+        &self,
+        text: &str,
+        mut edit: impl FnMut(usize, String) -> Option<String>,
+    ) -> Option<String> {
+        let mut changed = false;
+        let mut lines = vec![];
+        for (ind, line) in self.source.split_inclusive('\n').enumerate() {
+            let (body, newline) = match line.strip_suffix('\n') {
+                Some(b) => (b, "\n"),
+                None => (line, ""),
+            };
+            match edit(ind + 1, body.to_string()) {
+                Some(new) if new == body => lines.push((ind, line.to_string())),
+                Some(new) => {
+                    changed = true;
+                    lines.push((ind, new + newline))
+                }
+                None => changed = true,
+            }
+        }
+        if !changed {
+            return None;
+        }
+
+        // the last line keeps the ending of source
+        if !self.source.ends_with('\n')
+            && let Some((_, l)) = lines.last_mut()
+            && l.ends_with('\n')
+        {
+            l.pop();
+        }
+
+        let Some(elements) = &self.elements else {
+            return serde_json::to_string(&lines.into_iter().map(|(_, l)| l).collect::<String>())
+                .ok();
+        };
+        let (first, last) = (elements.first()?, elements.last()?);
+        let open = &text[self.span.start..first.0.start];
+        let close = &text[last.0.end..self.span.end];
+        let sep = match elements.get(1) {
+            Some(second) => text[first.0.end..second.0.start].to_string(),
+            None => format!(",{}", &open[1..]),
+        };
+        if lines.is_empty() {
+            return Some("[]".to_string());
+        }
+
+        let elements = lines
+            .into_iter()
+            .map(|(ind, l)| match elements.get(ind) {
+                Some((r, old)) if *old == l => Some(text[r.clone()].to_string()),
+                _ => serde_json::to_string(&l).ok(),
+            })
+            .collect::<Option<Vec<_>>>()?;
+        Some(format!("{}{}{}", open, elements.join(&sep), close))
+    }
+}
+
+/// the text where the crumb's line numbers count: the source of `cell` in
+/// notebook, or the whole file if the crumb isn't in cell
+pub(crate) fn read_source(file_path: &str, cell: Option<usize>) -> io::Result<String> {
+    let text = fs::read_to_string(file_path)?;
+    let Some(cell) = cell else {
+        return Ok(text);
+    };
+    let notebook = Notebook::parse(&text)
+        .map_err(|e| io::Error::other(format!("cannot parse notebook {}: {}", file_path, e)))?;
+    notebook
+        .cells()
+        .get(cell.wrapping_sub(1))
+        .map(|c| c.source.clone())
+        .ok_or_else(|| io::Error::other(format!("no cell {} in {}", cell, file_path)))
+}
+
+/// rewrite every line of cells in notebook on file_path by `edit` like
+/// `Notebook::edit_lines`
+pub(crate) fn edit_lines_on(
+    file_path: &str,
+    edit: impl FnMut(usize, usize, String) -> Option<String>,
+) -> io::Result<()> {
+    let mut text = String::new();
+    fs::File::open(file_path)?.read_to_string(&mut text)?;
+    let new_text = Notebook::parse(&text)
+        .map_err(|e| io::Error::other(format!("cannot parse notebook {}: {}", file_path, e)))?
+        .edit_lines(edit);
+
+    OpenOptions::new()
+        .write(true)
+        .truncate(true)
+        .open(file_path)?
+        .write_all(new_text.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOTEBOOK: &str = r##"{
+ "cells": [
+  {
+   "cell_type": "markdown",
+   "metadata": {},
+   "source": "<!--:= MARK: doc -->"
+  },
+  {
+   "cell_type": "code",
+   "execution_count": 1,
+   "metadata": {},
+   "outputs": [],
+   "source": [
+    "import os  #:= TODO: café\n",
+    "#:= FIXME: b\n",
+    "x = 1"
+   ]
+  }
+ ],
+ "metadata": {
+  "kernelspec": {"display_name": "Python 3", "language": "python", "name": "python3"},
+  "language_info": {"name": "python", "file_extension": ".py"}
+ },
+ "nbformat": 4,
+ "nbformat_minor": 5
+}
+"##;
+
+    #[test]
+    fn test_parse_notebook() {
+        let nb = Notebook::parse(NOTEBOOK).unwrap();
+        assert_eq!(nb.languages(), ["py", "py", "py"]);
+        assert_eq!(nb.cells().len(), 2);
+        assert_eq!(nb.cells()[0].kind, CellKind::Markdown);
+        assert_eq!(nb.cells()[0].source, "<!--:= MARK: doc -->");
+        assert_eq!(nb.cells()[1].index, 2);
+        assert_eq!(
+            nb.cells()[1].source,
+            "import os  #:= TODO: café\n#:= FIXME: b\nx = 1"
+        );
+
+        assert!(Notebook::parse("{}").is_err());
+    }
+
+    #[test]
+    fn test_edit_lines() {
+        let nb = Notebook::parse(NOTEBOOK).unwrap();
+        assert_eq!(nb.edit_lines(|_, _, l| Some(l)), NOTEBOOK);
+
+        // only the changed elements are rewritten
+        let new = nb.edit_lines(|cell, line_num, l| match (cell, line_num) {
+            (2, 2) => None,
+            (2, 3) => Some("x = 2".to_string()),
+            (1, 1) => Some("<!-- MARK: doc -->".to_string()),
+            _ => Some(l),
+        });
+        assert_eq!(
+            new,
+            NOTEBOOK
+                .replace("\"<!--:= MARK", "\"<!-- MARK")
+                .replace("    \"#:= FIXME: b\\n\",\n", "")
+                .replace("x = 1", "x = 2")
+        );
+
+        // the new last line has no newline
+        let new = Notebook::parse(&new)
+            .unwrap()
+            .edit_lines(|cell, line_num, l| ((cell, line_num) != (2, 2)).then_some(l));
+        assert!(new.contains("\"import os  #:= TODO: café\"\n   ]"));
+    }
+}
@@ -0,0 +1,1009 @@
+//! The output formats those need all breads to make one document
+
+use super::config::{Column, GroupBy, KeywordRegistry, SeverityTable, language_name};
+use super::datatypes::*;
+use super::lsp::utf16_len;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::{Component, Path};
+
+/// the version of json/jsonl output schema. Bump it when the
+/// fields of records below changed incompatibly
+pub const JSON_SCHEMA_VERSION: u32 = 1;
+
+/// the json output document
+#[derive(Debug, Serialize, Deserialize)]
+pub struct JsonDocument {
+    pub schema_version: u32,
+    pub breads: Vec<BreadRecord>,
+}
+
+impl JsonDocument {
+    pub fn new(breads: &[Bread]) -> Self {
+        Self {
+            schema_version: JSON_SCHEMA_VERSION,
+            breads: breads.iter().map(BreadRecord::from).collect(),
+        }
+    }
+}
+
+/// the record of one bread in json output
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BreadRecord {
+    pub file_path: String,
+    pub language: String,
+    pub crumbs: Vec<CrumbRecord>,
+}
+
+impl From<&Bread> for BreadRecord {
+    fn from(b: &Bread) -> Self {
+        Self {
+            file_path: b.file_path.clone(),
+            language: b.language(),
+            crumbs: b.crumbs.iter().map(CrumbRecord::from).collect(),
+        }
+    }
+}
+
+/// the record of one crumb in json/jsonl output
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrumbRecord {
+    /// the line number of the crumb, starts from 1
+    pub line_num: usize,
+    /// the cell of notebook the crumb in, starts from 1
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cell: Option<usize>,
+    /// the column of the crumb comment symbol, starts from 1
+    pub column: usize,
+    /// the byte offset of the crumb comment symbol in line, starts from 0
+    pub position: usize,
+    /// the last line number of the crumb, different with `line_num` when it has tails
+    pub end_line: usize,
+    pub keyword: Option<String>,
+    /// the content for showing, including tails' content
+    pub view_content: String,
+    /// the original content after `:=` of the first line
+    pub content: String,
+    pub ignore: bool,
+    /// the plain keyword comment without `:=` found by `--compat`
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub plain: bool,
+    /// only given by users to `apply`, remove this crumb
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub remove: bool,
+    /// the line numbers of the tails
+    pub tail_lines: Vec<usize>,
+    /// the lines around the crumb when `-r/--range` is given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub range_content: Option<Vec<(usize, String)>>,
+    /// the git blame of the crumb when `--blame` is given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blame: Option<Blame>,
+    /// the git blame of the tails, same order as `tail_lines`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tail_blames: Vec<Option<Blame>>,
+}
+
+impl From<&Crumb> for CrumbRecord {
+    fn from(c: &Crumb) -> Self {
+        let all_lines = c.all_lines_num();
+        Self {
+            line_num: c.line_num,
+            cell: c.cell,
+            column: c.position + 1,
+            position: c.position,
+            end_line: *all_lines.last().unwrap(),
+            keyword: c.keyword.clone(),
+            view_content: c.view_content.clone(),
+            content: c.content.clone(),
+            ignore: c.is_ignore(),
+            plain: c.is_plain(),
+            remove: false,
+            tail_lines: all_lines[1..].to_vec(),
+            range_content: c.range_content.clone(),
+            blame: c.blame.clone(),
+            tail_blames: if c.blame.is_some() {
+                c.tails_blames()
+            } else {
+                vec![]
+            },
+        }
+    }
+}
+
+/// one line of the jsonl output, every crumb is one line
+#[derive(Debug, Serialize, Deserialize)]
+pub struct JsonlRecord {
+    pub schema_version: u32,
+    pub file_path: String,
+    pub language: String,
+    #[serde(flatten)]
+    pub crumb: CrumbRecord,
+}
+
+/// make jsonl records of this bread
+pub fn to_jsonl_records(b: &Bread) -> impl Iterator<Item = JsonlRecord> + '_ {
+    let language = b.language();
+    b.crumbs.iter().map(move |c| JsonlRecord {
+        schema_version: JSON_SCHEMA_VERSION,
+        file_path: b.file_path.clone(),
+        language: language.clone(),
+        crumb: CrumbRecord::from(c),
+    })
+}
+
+/// the html report template, `{{TITLE}}` and `{{DATA}}` are replaced
+/// by the title and the json document of breads
+const HTML_TEMPLATE: &str = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>{{TITLE}}</title>
+<style>
+body { font-family: sans-serif; margin: 2em; color: #222; }
+h1 { font-size: 1.4em; }
+#tabs button { border: 1px solid #aaa; background: #f4f4f4; padding: 0.3em 0.8em; margin: 0 0.2em 0.5em 0; cursor: pointer; }
+#tabs button.active { background: #333; color: #fff; }
+#filter { width: 30em; padding: 0.3em; margin-bottom: 0.8em; }
+table { border-collapse: collapse; width: 100%; }
+th, td { border-bottom: 1px solid #ddd; padding: 0.3em 0.6em; text-align: left; vertical-align: top; }
+th { cursor: pointer; user-select: none; background: #f4f4f4; }
+th.asc::after { content: " \25B2"; }
+th.desc::after { content: " \25BC"; }
+tr.ignored { color: #999; }
+pre { margin: 0.3em 0; background: #f8f8f8; padding: 0.4em; overflow-x: auto; }
+</style>
+</head>
+<body>
+<h1>{{TITLE}}</h1>
+<div id="tabs"></div>
+<input id="filter" type="search" placeholder="Filter by file, keyword or text">
+<table>
+<thead><tr>
+<th data-key="file_path">File</th>
+<th data-key="line_num">Line</th>
+<th data-key="keyword">Keyword</th>
+<th data-key="view_content">Text</th>
+<th data-key="ignore">Ignored</th>
+<th data-key="author">Author</th>
+<th data-key="timestamp">Date</th>
+</tr></thead>
+<tbody id="crumbs"></tbody>
+</table>
+<script type="application/json" id="data">{{DATA}}</script>
+<script>
+(function () {
+  var doc = JSON.parse(document.getElementById("data").textContent);
+  var rows = [];
+  doc.breads.forEach(function (b) {
+    b.crumbs.forEach(function (c) {
+      rows.push({
+        file_path: b.file_path,
+        line_num: c.line_num,
+        keyword: c.keyword || "",
+        view_content: c.view_content,
+        ignore: c.ignore,
+        author: c.blame ? c.blame.author : "",
+        timestamp: c.blame ? c.blame.timestamp : 0,
+        range_content: c.range_content || null
+      });
+    });
+  });
+
+  var state = { tab: null, filter: "", key: null, asc: true };
+
+  function el(tag, text) {
+    var e = document.createElement(tag);
+    if (text !== undefined) e.textContent = text;
+    return e;
+  }
+
+  function render() {
+    var body = document.getElementById("crumbs");
+    body.innerHTML = "";
+    var filter = state.filter.toLowerCase();
+    var shown = rows.filter(function (r) {
+      if (state.tab !== null && r.keyword !== state.tab) return false;
+      if (!filter) return true;
+      return (r.file_path + " " + r.keyword + " " + r.view_content + " " + r.author).toLowerCase().indexOf(filter) >= 0;
+    });
+    if (state.key) {
+      shown.sort(function (a, b) {
+        var x = a[state.key], y = b[state.key];
+        var r = x < y ? -1 : x > y ? 1 : 0;
+        return state.asc ? r : -r;
+      });
+    }
+    shown.forEach(function (r) {
+      var tr = el("tr");
+      if (r.ignore) tr.className = "ignored";
+      tr.appendChild(el("td", r.file_path));
+      tr.appendChild(el("td", r.line_num));
+      tr.appendChild(el("td", r.keyword));
+      var text = el("td");
+      if (r.range_content) {
+        var details = el("details");
+        details.appendChild(el("summary", r.view_content));
+        details.appendChild(el("pre", r.range_content.map(function (l) {
+          return l[0] + ": " + l[1].replace(/\r?\n$/, "");
+        }).join("\n")));
+        text.appendChild(details);
+      } else {
+        text.textContent = r.view_content;
+      }
+      tr.appendChild(text);
+      tr.appendChild(el("td", r.ignore ? "yes" : ""));
+      tr.appendChild(el("td", r.author));
+      tr.appendChild(el("td", r.timestamp ? new Date(r.timestamp * 1000).toISOString().slice(0, 10) : ""));
+      body.appendChild(tr);
+    });
+  }
+
+  var tabs = document.getElementById("tabs");
+  var keywords = [];
+  rows.forEach(function (r) {
+    if (keywords.indexOf(r.keyword) < 0) keywords.push(r.keyword);
+  });
+  [null].concat(keywords).forEach(function (k) {
+    var count = rows.filter(function (r) { return k === null || r.keyword === k; }).length;
+    var button = el("button", (k === null ? "All" : k === "" ? "No keyword" : k) + " (" + count + ")");
+    if (k === null) button.className = "active";
+    button.addEventListener("click", function () {
+      state.tab = k;
+      Array.prototype.forEach.call(tabs.children, function (b) { b.className = ""; });
+      button.className = "active";
+      render();
+    });
+    tabs.appendChild(button);
+  });
+
+  document.getElementById("filter").addEventListener("input", function (e) {
+    state.filter = e.target.value;
+    render();
+  });
+
+  Array.prototype.forEach.call(document.querySelectorAll("th"), function (th) {
+    th.addEventListener("click", function () {
+      var key = th.getAttribute("data-key");
+      state.asc = state.key === key ? !state.asc : true;
+      state.key = key;
+      Array.prototype.forEach.call(document.querySelectorAll("th"), function (h) { h.className = ""; });
+      th.className = state.asc ? "asc" : "desc";
+      render();
+    });
+  });
+
+  render();
+})();
+</script>
+</body>
+</html>
+"#;
+
+/// make the self-contained html report of all breads
+pub fn to_html(breads: &[Bread]) -> Result<String, String> {
+    let data = serde_json::to_string(&JsonDocument::new(breads)).map_err(|e| e.to_string())?;
+    Ok(HTML_TEMPLATE
+        .replace("{{TITLE}}", "codeitlater crumbs")
+        // the json is inside <script>, it cannot close the tag or open a
+        // comment. These characters are only in the json strings
+        .replace(
+            "{{DATA}}",
+            &data
+                .replace('<', "\\u003c")
+                .replace('>', "\\u003e")
+                .replace('&', "\\u0026"),
+        ))
+}
+
+/// the separator of delimited output
+#[derive(Clone, Copy, Debug)]
+pub enum Delimiter {
+    Comma,
+    Tab,
+}
+
+impl Delimiter {
+    fn as_char(&self) -> char {
+        match self {
+            Delimiter::Comma => ',',
+            Delimiter::Tab => '\t',
+        }
+    }
+
+    /// csv quotes the field (RFC 4180), tsv escapes the special chars
+    fn escape(&self, field: &str) -> String {
+        match self {
+            Delimiter::Comma => {
+                if field.contains([',', '"', '\n', '\r']) {
+                    format!("\"{}\"", field.replace('"', "\"\""))
+                } else {
+                    field.to_string()
+                }
+            }
+            Delimiter::Tab => field
+                .replace('\\', "\\\\")
+                .replace('\t', "\\t")
+                .replace('\n', "\\n")
+                .replace('\r', "\\r"),
+        }
+    }
+
+    fn join(&self, fields: impl Iterator<Item = String>) -> String {
+        fields
+            .map(|f| self.escape(&f))
+            .collect::<Vec<_>>()
+            .join(&self.as_char().to_string())
+    }
+}
+
+/// the header row of delimited output
+pub fn delimited_header(columns: &[Column], delimiter: Delimiter) -> String {
+    delimiter.join(columns.iter().map(|c| c.as_str().to_string()))
+}
+
+/// the rows of this bread in delimited output, one crumb one row
+pub fn delimited_rows<'a>(
+    b: &'a Bread,
+    columns: &'a [Column],
+    delimiter: Delimiter,
+) -> impl Iterator<Item = String> + 'a {
+    b.crumbs.iter().map(move |c| {
+        delimiter.join(columns.iter().map(|col| match col {
+            Column::Path => b.file_path.clone(),
+            Column::Cell => c.cell.map(|c| c.to_string()).unwrap_or_default(),
+            Column::Line => c.line_num.to_string(),
+            Column::Column => (c.position + 1).to_string(),
+            Column::Keyword => c.keyword.clone().unwrap_or_default(),
+            Column::Text => c.view_content.clone(),
+            Column::Ignored => c.is_ignore().to_string(),
+            Column::Tails => c.all_lines_num()[1..]
+                .iter()
+                .map(|l| l.to_string())
+                .collect::<Vec<_>>()
+                .join(" "),
+            Column::Author => c.blame.as_ref().map(|b| b.author.clone()).unwrap_or_default(),
+            Column::Email => c.blame.as_ref().map(|b| b.email.clone()).unwrap_or_default(),
+            Column::Commit => c.blame.as_ref().map(|b| b.commit.clone()).unwrap_or_default(),
+            Column::Date => c.blame.as_ref().map(|b| b.date()).unwrap_or_default(),
+        }))
+    })
+}
+
+/// how many files with the most crumbs show in stats
+const STATS_TOP_FILES: usize = 10;
+
+/// the summary statistics of crumbs
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct Stats {
+    pub total: usize,
+    pub active: usize,
+    pub ignored: usize,
+    pub files: usize,
+    /// all counts below are sorted by count descending, then by name
+    pub keywords: Vec<(String, usize)>,
+    pub directories: Vec<(String, usize)>,
+    pub extensions: Vec<(String, usize)>,
+    /// the language names of extensions, empty if it is unknown
+    pub languages: Vec<(String, usize)>,
+    pub top_files: Vec<(String, usize)>,
+}
+
+impl Stats {
+    pub fn new(breads: &[Bread]) -> Self {
+        let mut stats = Self::default();
+        let mut keywords = HashMap::new();
+        let mut directories = HashMap::new();
+        let mut extensions = HashMap::new();
+        let mut languages = HashMap::new();
+        let mut files = vec![];
+
+        for b in breads {
+            let count = b.crumbs.len();
+            let path = Path::new(&b.file_path);
+            stats.files += 1;
+            stats.total += count;
+            files.push((b.file_path.clone(), count));
+
+            *directories.entry(top_level_dir(path)).or_insert(0) += count;
+            let extension = path
+                .extension()
+                .map(|e| e.to_string_lossy().to_string())
+                .unwrap_or_default();
+            *languages
+                .entry(language_name(&extension).unwrap_or_default().to_string())
+                .or_insert(0) += count;
+            *extensions.entry(extension).or_insert(0) += count;
+
+            for c in &b.crumbs {
+                if c.is_ignore() {
+                    stats.ignored += 1
+                } else {
+                    stats.active += 1
+                }
+                *keywords
+                    .entry(c.keyword.clone().unwrap_or_default())
+                    .or_insert(0) += 1;
+            }
+        }
+
+        stats.keywords = sorted_counts(keywords.into_iter());
+        stats.directories = sorted_counts(directories.into_iter());
+        stats.extensions = sorted_counts(extensions.into_iter());
+        stats.languages = sorted_counts(languages.into_iter());
+        stats.top_files = sorted_counts(files.into_iter());
+        stats.top_files.truncate(STATS_TOP_FILES);
+        stats
+    }
+}
+
+/// the first directory of this path, "." if the file is in current directory
+fn top_level_dir(path: &Path) -> String {
+    let mut components = path
+        .components()
+        .filter(|c| !matches!(c, Component::CurDir));
+    match (components.next(), components.next()) {
+        (Some(first), Some(_)) => first.as_os_str().to_string_lossy().to_string(),
+        _ => ".".to_string(),
+    }
+}
+
+fn sorted_counts(counts: impl Iterator<Item = (String, usize)>) -> Vec<(String, usize)> {
+    let mut counts = counts.collect::<Vec<_>>();
+    counts.sort_by(|(a, x), (b, y)| y.cmp(x).then(a.cmp(b)));
+    counts
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Total: {} crumbs in {} files ({} active, {} ignored)",
+            self.total, self.files, self.active, self.ignored
+        )?;
+
+        for (title, counts, empty_name) in [
+            ("By keyword", &self.keywords, "(no keyword)"),
+            ("By directory", &self.directories, "."),
+            ("By extension", &self.extensions, "(no extension)"),
+            ("By language", &self.languages, "(unknown)"),
+            ("Files with most crumbs", &self.top_files, ""),
+        ] {
+            writeln!(f, "\n{}:", title)?;
+            for (name, count) in counts {
+                writeln!(
+                    f,
+                    "  {:>6}  {}",
+                    count,
+                    if name.is_empty() { empty_name } else { name }
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// the summary of deleting/restoring crumbs without prompt
+#[derive(Debug, Serialize)]
+pub struct ChangeSummary {
+    /// delete, restore or adopt
+    pub action: String,
+    /// false if nothing changed, the summary shows what would change
+    pub applied: bool,
+    pub total: usize,
+    pub files: Vec<FileChange>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FileChange {
+    pub file_path: String,
+    pub crumbs: Vec<ChangedCrumb>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ChangedCrumb {
+    pub id: String,
+    pub line_num: usize,
+    pub end_line: usize,
+    pub keyword: Option<String>,
+    pub content: String,
+}
+
+impl ChangeSummary {
+    pub fn new(action: &str, applied: bool, breads: &[Bread]) -> Self {
+        Self {
+            action: action.to_string(),
+            applied,
+            total: breads.iter().map(|b| b.crumbs.len()).sum(),
+            files: breads
+                .iter()
+                .map(|b| FileChange {
+                    file_path: b.file_path.clone(),
+                    crumbs: b
+                        .crumbs
+                        .iter()
+                        .map(|c| ChangedCrumb {
+                            id: b.crumb_id(c),
+                            line_num: c.line_num,
+                            end_line: *c.all_lines_num().last().unwrap(),
+                            keyword: c.keyword.clone(),
+                            content: c.view_content.clone(),
+                        })
+                        .collect(),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl fmt::Display for ChangeSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let verb = match (self.applied, self.action.as_str()) {
+            (true, "delete") => "deleted",
+            (true, "adopt") => "adopted",
+            (true, _) => "restored",
+            (false, action) => &format!("would {}", action),
+        };
+        for file in &self.files {
+            writeln!(
+                f,
+                "{} {} crumbs in {}",
+                verb,
+                file.crumbs.len(),
+                file.file_path
+            )?;
+            for c in &file.crumbs {
+                match &c.keyword {
+                    Some(k) => writeln!(f, "  {}: {}: {}", c.id, k, c.content)?,
+                    None => writeln!(f, "  {}: {}", c.id, c.content)?,
+                }
+            }
+        }
+        writeln!(f, "Total: {} {} crumbs", verb, self.total)
+    }
+}
+
+/// the keyword and all crumbs (with their file path) of this keyword
+type KeywordGroup<'a> = (Option<&'a str>, Vec<(&'a str, &'a Crumb)>);
+
+/// make the markdown document of all breads
+pub fn to_markdown(breads: &[Bread], group_by: &GroupBy, keywords: &KeywordRegistry) -> String {
+    let mut content = String::from("# Crumbs\n\n");
+    match group_by {
+        GroupBy::File => breads.iter().for_each(|b| {
+            content.push_str(&b.to_markdown());
+            content.push('\n');
+        }),
+        GroupBy::Keyword => {
+            // keep the keywords order they first show up
+            let mut groups: Vec<KeywordGroup> = vec![];
+            for b in breads {
+                for c in &b.crumbs {
+                    let kw = c.keyword.as_deref();
+                    match groups.iter_mut().find(|(k, _)| *k == kw) {
+                        Some((_, cs)) => cs.push((&b.file_path, c)),
+                        None => groups.push((kw, vec![(&b.file_path, c)])),
+                    }
+                }
+            }
+
+            for (kw, crumbs) in groups {
+                content.push_str(&format!("## {}\n\n", kw.unwrap_or("No keyword")));
+                if let Some(d) = kw.and_then(|k| keywords.description(k)) {
+                    content.push_str(&format!("> {}\n\n", d));
+                }
+                crumbs
+                    .iter()
+                    .for_each(|(f, c)| content.push_str(&c.to_markdown(f, true)));
+                content.push('\n');
+            }
+        }
+    }
+    content
+}
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+const SARIF_VERSION: &str = "2.1.0";
+
+/// the rule id of the crumbs without keyword
+const SARIF_NO_KEYWORD_RULE: &str = "crumb";
+
+/// SARIF 2.1.0 log, only the parts codeitlater uses
+#[derive(Debug, Serialize)]
+pub struct SarifLog<'a> {
+    #[serde(rename = "$schema")]
+    schema: &'static str,
+    version: &'static str,
+    runs: Vec<SarifRun<'a>>,
+}
+
+#[derive(Debug, Serialize)]
+struct SarifRun<'a> {
+    tool: SarifTool,
+    results: Vec<SarifResult<'a>>,
+}
+
+#[derive(Debug, Serialize)]
+struct SarifTool {
+    driver: SarifDriver,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifDriver {
+    name: &'static str,
+    version: &'static str,
+    information_uri: &'static str,
+    rules: Vec<SarifRule>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifRule {
+    id: String,
+    short_description: SarifMessage<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifResult<'a> {
+    rule_id: &'a str,
+    rule_index: usize,
+    level: &'static str,
+    message: SarifMessage<&'a str>,
+    locations: Vec<SarifLocation<'a>>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    suppressions: Vec<SarifSuppression>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    properties: Option<SarifBlameProperties<'a>>,
+}
+
+#[derive(Debug, Serialize)]
+struct SarifBlameProperties<'a> {
+    author: &'a str,
+    email: &'a str,
+    commit: &'a str,
+    timestamp: i64,
+}
+
+#[derive(Debug, Serialize)]
+struct SarifMessage<T> {
+    text: T,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifLocation<'a> {
+    physical_location: SarifPhysicalLocation<'a>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    logical_locations: Vec<SarifLogicalLocation>,
+}
+
+/// the cell of notebook, the region of file cannot locate it
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifLogicalLocation {
+    name: String,
+    fully_qualified_name: String,
+    kind: &'static str,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifPhysicalLocation<'a> {
+    artifact_location: SarifArtifactLocation<'a>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    region: Option<SarifRegion>,
+}
+
+#[derive(Debug, Serialize)]
+struct SarifArtifactLocation<'a> {
+    uri: &'a str,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifRegion {
+    start_line: usize,
+    start_column: usize,
+    end_line: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct SarifSuppression {
+    kind: &'static str,
+}
+
+/// make the SARIF log, every crumb is one result and every keyword is one rule
+pub fn to_sarif<'a>(
+    breads: &'a [Bread],
+    severities: &SeverityTable,
+    keywords: &KeywordRegistry,
+) -> SarifLog<'a> {
+    let mut rules: Vec<String> = vec![];
+    let mut results = vec![];
+
+    for b in breads {
+        // the crumb position is the byte offset, but the SARIF column counts
+        // the utf-16 code units by default
+        let text = fs::read_to_string(&b.file_path).unwrap_or_default();
+        let lines = text.lines().collect::<Vec<_>>();
+        for c in &b.crumbs {
+            let rule_id = c.keyword.as_deref().unwrap_or(SARIF_NO_KEYWORD_RULE);
+            let rule_index = match rules.iter().position(|r| r == rule_id) {
+                Some(i) => i,
+                None => {
+                    rules.push(rule_id.to_string());
+                    rules.len() - 1
+                }
+            };
+
+            results.push(SarifResult {
+                rule_id,
+                rule_index,
+                level: severities.get(c.keyword.as_deref()).sarif_level(),
+                message: SarifMessage {
+                    text: &c.view_content,
+                },
+                locations: vec![SarifLocation {
+                    physical_location: SarifPhysicalLocation {
+                        artifact_location: SarifArtifactLocation {
+                            uri: markdown_link_path(&b.file_path),
+                        },
+                        region: c.cell.is_none().then(|| SarifRegion {
+                            start_line: c.line_num,
+                            start_column: lines
+                                .get(c.line_num - 1)
+                                .and_then(|l| l.get(..c.position))
+                                .map_or(c.position, utf16_len)
+                                + 1,
+                            end_line: *c.all_lines_num().last().unwrap(),
+                        }),
+                    },
+                    logical_locations: c
+                        .cell
+                        .map(|cell| SarifLogicalLocation {
+                            name: format!("cell {}", cell),
+                            fully_qualified_name: format!(
+                                "{}:{}",
+                                markdown_link_path(&b.file_path),
+                                c.location()
+                            ),
+                            kind: "cell",
+                        })
+                        .into_iter()
+                        .collect(),
+                }],
+                suppressions: if c.is_ignore() {
+                    vec![SarifSuppression { kind: "inSource" }]
+                } else {
+                    vec![]
+                },
+                properties: c.blame.as_ref().map(|b| SarifBlameProperties {
+                    author: &b.author,
+                    email: &b.email,
+                    commit: &b.commit,
+                    timestamp: b.timestamp,
+                }),
+            })
+        }
+    }
+
+    SarifLog {
+        schema: SARIF_SCHEMA,
+        version: SARIF_VERSION,
+        runs: vec![SarifRun {
+            tool: SarifTool {
+                driver: SarifDriver {
+                    name: "codeitlater",
+                    version: env!("CARGO_PKG_VERSION"),
+                    information_uri: env!("CARGO_PKG_REPOSITORY"),
+                    rules: rules
+                        .into_iter()
+                        .map(|id| SarifRule {
+                            short_description: SarifMessage {
+                                text: match keywords.description(&id) {
+                                    _ if id == SARIF_NO_KEYWORD_RULE => {
+                                        "crumb without keyword".to_string()
+                                    }
+                                    Some(d) => d.to_string(),
+                                    None => format!("{} crumb", id),
+                                },
+                            },
+                            id,
+                        })
+                        .collect(),
+                },
+            },
+            results,
+        }],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use regex::Regex;
+
+    #[test]
+    fn test_to_markdown_group_by_keyword() {
+        let re = Regex::new("(TODO|MARK):\\s*(.*)").unwrap();
+        let mut a = Crumb::new(1, 0, "TODO: a".to_string(), "//".to_string());
+        a.filter_keywords(&re);
+        let mut b = Crumb::new(2, 0, "MARK: b".to_string(), "//".to_string());
+        b.filter_keywords(&re);
+        let mut c = Crumb::new(5, 0, "TODO: c".to_string(), "#".to_string());
+        c.filter_keywords(&re);
+        let d = Crumb::new(6, 0, "d".to_string(), "#".to_string());
+
+        let breads = vec![
+            Bread::new("./a.rs".to_string(), vec![a, b]),
+            Bread::new("./b.py".to_string(), vec![c, d]),
+        ];
+
+        assert_eq!(
+            to_markdown(&breads, &GroupBy::Keyword, &Default::default()),
+            r#"# Crumbs
+
+## TODO
+
+- [ ] [a.rs#L1](a.rs#L1) **TODO**: a
+- [ ] [b.py#L5](b.py#L5) **TODO**: c
+
+## MARK
+
+- [ ] [a.rs#L2](a.rs#L2) **MARK**: b
+
+## No keyword
+
+- [ ] [b.py#L6](b.py#L6) d
+
+"#
+        );
+
+        assert!(
+            to_markdown(&breads, &GroupBy::File, &Default::default())
+                .contains("## [./b.py](b.py)\n\n")
+        );
+
+        let keywords = ["TODO=warning::do it later".parse().unwrap()]
+            .into_iter()
+            .collect();
+        assert!(
+            to_markdown(&breads, &GroupBy::Keyword, &keywords)
+                .contains("## TODO\n\n> do it later\n\n- [ ] [a.rs#L1]")
+        );
+    }
+
+    #[test]
+    fn test_to_html() {
+        let a = Crumb::new(1, 0, "</script><!--a&b".to_string(), "//".to_string());
+        let breads = vec![Bread::new("./a.rs".to_string(), vec![a])];
+        let html = to_html(&breads).unwrap();
+
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains(r#""view_content":"\u003c/script\u003e\u003c!--a\u0026b""#));
+        assert_eq!(html.matches("</script>").count(), 2);
+        // no external assets
+        assert!(!html.contains("src="));
+        assert!(!html.contains("href="));
+    }
+
+    #[test]
+    fn test_delimited() {
+        let mut a = Crumb::new(1, 3, "TODO: a, \"b\"...".to_string(), "//".to_string());
+        a.filter_keywords(&Regex::new("(TODO):\\s*(.*)").unwrap());
+        a.add_tail(Crumb::new(2, 3, "c\td".to_string(), "//".to_string()));
+        let b = Bread::new("./a.rs".to_string(), vec![a]);
+
+        assert_eq!(
+            delimited_header(&Column::DEFAULT, Delimiter::Comma),
+            "path,cell,line,column,keyword,text,ignored,tails"
+        );
+        assert_eq!(
+            delimited_rows(&b, &Column::DEFAULT, Delimiter::Comma).collect::<Vec<_>>(),
+            vec!["./a.rs,,1,4,TODO,\"a, \"\"b\"\" c\td\",false,2"]
+        );
+        assert_eq!(
+            delimited_rows(&b, &[Column::Text, Column::Line], Delimiter::Tab)
+                .collect::<Vec<_>>(),
+            vec!["a, \"b\" c\\td\t1"]
+        );
+
+        // the cell of notebook
+        let nb = Bread::new(
+            "./a.ipynb".to_string(),
+            vec![Crumb::new(2, 0, "b".to_string(), "#".to_string()).in_cell(3)],
+        );
+        assert_eq!(
+            delimited_rows(&nb, &Column::DEFAULT, Delimiter::Comma).collect::<Vec<_>>(),
+            vec!["./a.ipynb,3,2,1,,b,false,"]
+        );
+
+        assert_eq!(Delimiter::Comma.escape("a\nb"), "\"a\nb\"");
+        assert_eq!(Delimiter::Tab.escape("a\nb\\"), "a\\nb\\\\");
+    }
+
+    #[test]
+    fn test_stats() {
+        let re = Regex::new("(TODO|MARK):\\s*(.*)").unwrap();
+        let mut a = Crumb::new(1, 0, "TODO: a".to_string(), "//".to_string());
+        a.filter_keywords(&re);
+        let mut b = Crumb::new(2, 0, "!MARK: b".to_string(), "//".to_string()).add_ignore_flag();
+        b.filter_keywords(&re);
+        let mut c = Crumb::new(5, 0, "TODO: c".to_string(), "#".to_string());
+        c.filter_keywords(&re);
+        let d = Crumb::new(6, 0, "d".to_string(), "#".to_string());
+
+        let breads = vec![
+            Bread::new("./src/a.rs".to_string(), vec![a, b]),
+            Bread::new("./b.py".to_string(), vec![c]),
+            Bread::new("src/sub/c.py".to_string(), vec![d]),
+        ];
+
+        let stats = Stats::new(&breads);
+        assert_eq!(stats.total, 4);
+        assert_eq!(stats.active, 3);
+        assert_eq!(stats.ignored, 1);
+        assert_eq!(stats.files, 3);
+        assert_eq!(
+            stats.keywords,
+            vec![
+                ("TODO".to_string(), 2),
+                ("".to_string(), 1),
+                ("MARK".to_string(), 1)
+            ]
+        );
+        assert_eq!(
+            stats.directories,
+            vec![("src".to_string(), 3), (".".to_string(), 1)]
+        );
+        assert_eq!(
+            stats.extensions,
+            vec![("py".to_string(), 2), ("rs".to_string(), 2)]
+        );
+        assert_eq!(
+            stats.languages,
+            vec![("python".to_string(), 2), ("rust".to_string(), 2)]
+        );
+        assert_eq!(stats.top_files[0], ("./src/a.rs".to_string(), 2));
+
+        // the extensions of one language are counted together
+        let e = Crumb::new(1, 0, "e".to_string(), "<!--".to_string());
+        let markup = Stats::new(&[
+            Bread::new("a.htm".to_string(), vec![e.clone()]),
+            Bread::new("b.html".to_string(), vec![e.clone()]),
+            Bread::new("c.unknown".to_string(), vec![e]),
+        ]);
+        assert_eq!(
+            markup.languages,
+            vec![("html".to_string(), 2), ("".to_string(), 1)]
+        );
+
+        let human = stats.to_string();
+        assert!(human.starts_with("Total: 4 crumbs in 3 files (3 active, 1 ignored)\n"));
+        assert!(human.contains("\nBy keyword:\n       2  TODO\n       1  (no keyword)\n"));
+    }
+
+    #[test]
+    fn test_json_records() {
+        let mut a = Crumb::new(1, 3, "TODO: a...".to_string(), "//".to_string());
+        a.filter_keywords(&Regex::new("(TODO):\\s*(.*)").unwrap());
+        a.add_tail(Crumb::new(2, 3, "b".to_string(), "//".to_string()));
+        let breads = vec![Bread::new("./a.rs".to_string(), vec![a])];
+
+        assert_eq!(
+            serde_json::to_string(&JsonDocument::new(&breads)).unwrap(),
+            r#"{"schema_version":1,"breads":[{"file_path":"./a.rs","language":"rs","crumbs":[{"line_num":1,"column":4,"position":3,"end_line":2,"keyword":"TODO","view_content":"a b","content":"TODO: a...","ignore":false,"tail_lines":[2]}]}]}"#
+        );
+
+        let records = to_jsonl_records(&breads[0]).collect::<Vec<_>>();
+        assert_eq!(records.len(), 1);
+        assert_eq!(
+            serde_json::to_string(&records[0]).unwrap(),
+            r#"{"schema_version":1,"file_path":"./a.rs","language":"rs","line_num":1,"column":4,"position":3,"end_line":2,"keyword":"TODO","view_content":"a b","content":"TODO: a...","ignore":false,"tail_lines":[2]}"#
+        );
+    }
+}
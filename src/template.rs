@@ -0,0 +1,433 @@
+//! User defined output template
+//!
+//! The inline template (`--template`) is the template of every crumb. The
+//! template file (`--template-file`) has sections begin with the marker line,
+//! all of sections are optional:
+//!
+//! ```text
+//! [[header]]
+//! once at the beginning
+//! [[file_header]]
+//! every file before its crumbs
+//! [[crumb]]
+//! every crumb, {context} is replaced by the context section of all lines around
+//! [[context]]
+//! every line around the crumb (needs -r/--range)
+//! [[file_footer]]
+//! every file after its crumbs
+//! [[footer]]
+//! once at the end
+//! ```
+//!
+//! `{{` and `}}` are the escaped `{` and `}`. The line ends with `\` doesn't
+//! have the newline in the output.
+
+use super::config::SeverityTable;
+use super::datatypes::*;
+use std::str::FromStr;
+
+/// the placeholders can be used in templates
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Field {
+    Path,
+    Language,
+    Count,
+    Line,
+    Col,
+    EndLine,
+    Keyword,
+    Text,
+    Content,
+    Ignored,
+    Tails,
+    Severity,
+    Author,
+    Email,
+    Commit,
+    Date,
+    Context,
+    CtxLine,
+    CtxText,
+}
+
+impl FromStr for Field {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "path" => Field::Path,
+            "language" => Field::Language,
+            "count" => Field::Count,
+            "line" => Field::Line,
+            "col" => Field::Col,
+            "end_line" => Field::EndLine,
+            "keyword" => Field::Keyword,
+            "text" => Field::Text,
+            "content" => Field::Content,
+            "ignored" => Field::Ignored,
+            "tails" => Field::Tails,
+            "severity" => Field::Severity,
+            "author" => Field::Author,
+            "email" => Field::Email,
+            "commit" => Field::Commit,
+            "date" => Field::Date,
+            "context" => Field::Context,
+            "ctx_line" => Field::CtxLine,
+            "ctx_text" => Field::CtxText,
+            _ => return Err(format!("unknown placeholder {{{}}} in template", s)),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Piece {
+    Text(String),
+    Field(Field),
+}
+
+/// the parsed section of template
+#[derive(Clone, Debug, Default, PartialEq)]
+struct Section(Vec<Piece>);
+
+impl FromStr for Section {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut pieces = vec![];
+        let mut text = String::new();
+        let mut chars = s.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    text.push('{')
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    text.push('}')
+                }
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => name.push(c),
+                            None => return Err(format!("unclosed {{{} in template", name)),
+                        }
+                    }
+                    if !text.is_empty() {
+                        pieces.push(Piece::Text(std::mem::take(&mut text)))
+                    }
+                    pieces.push(Piece::Field(name.parse()?))
+                }
+                '}' => return Err("single } in template, use }} instead".to_string()),
+                _ => text.push(c),
+            }
+        }
+
+        if !text.is_empty() {
+            pieces.push(Piece::Text(text))
+        }
+        Ok(Section(pieces))
+    }
+}
+
+/// the crumb and its bread rendering right now
+struct Scope<'a> {
+    bread: &'a Bread,
+    crumb: Option<&'a Crumb>,
+    context_line: Option<&'a (usize, String)>,
+}
+
+/// user defined output template
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Template {
+    header: Section,
+    file_header: Section,
+    crumb: Section,
+    context: Section,
+    file_footer: Section,
+    footer: Section,
+}
+
+impl Template {
+    /// the inline template, which is the template of every crumb
+    pub fn inline(s: &str) -> Result<Self, String> {
+        Ok(Self {
+            crumb: format!("{}\n", s).parse()?,
+            ..Default::default()
+        })
+    }
+
+    /// parse the content of template file
+    pub fn from_file_content(content: &str) -> Result<Self, String> {
+        let mut template = Self::default();
+        let mut current: Option<&str> = None;
+        let mut buf = String::new();
+
+        let mut set_section = |name: Option<&str>, buf: &str| -> Result<(), String> {
+            let section = match name {
+                Some("header") => &mut template.header,
+                Some("file_header") => &mut template.file_header,
+                Some("crumb") => &mut template.crumb,
+                Some("context") => &mut template.context,
+                Some("file_footer") => &mut template.file_footer,
+                Some("footer") => &mut template.footer,
+                Some(n) => return Err(format!("unknown section [[{}]] in template", n)),
+                None if buf.trim().is_empty() => return Ok(()),
+                None => return Err("template content before any [[section]]".to_string()),
+            };
+            let parsed: Section = buf.parse()?;
+            // the context section is rendered by {context}, it cannot be in
+            // the other sections
+            if name != Some("crumb") && parsed.0.contains(&Piece::Field(Field::Context)) {
+                return Err(format!(
+                    "{{context}} in [[{}]] of template, it can only be in [[crumb]]",
+                    name.unwrap_or_default()
+                ));
+            }
+            *section = parsed;
+            Ok(())
+        };
+
+        for line in content.lines() {
+            if let Some(name) = line
+                .trim_end()
+                .strip_prefix("[[")
+                .and_then(|l| l.strip_suffix("]]"))
+            {
+                set_section(current, &buf)?;
+                current = Some(name);
+                buf.clear();
+            } else if let Some(l) = line.strip_suffix('\\') {
+                // line ends with \ doesn't have the newline
+                buf.push_str(l);
+            } else {
+                buf.push_str(line);
+                buf.push('\n');
+            }
+        }
+        set_section(current, &buf)?;
+
+        Ok(template)
+    }
+
+    fn render_section(
+        &self,
+        section: &Section,
+        scope: &Scope,
+        severities: &SeverityTable,
+        out: &mut String,
+    ) {
+        for p in &section.0 {
+            let field = match p {
+                Piece::Text(t) => {
+                    out.push_str(t);
+                    continue;
+                }
+                Piece::Field(f) => f,
+            };
+
+            let crumb = scope.crumb;
+            match field {
+                Field::Path => out.push_str(&scope.bread.file_path),
+                Field::Language => out.push_str(&scope.bread.language()),
+                Field::Count => out.push_str(&scope.bread.crumbs.len().to_string()),
+                Field::Context => {
+                    if let Some(range) = crumb.and_then(|c| c.range_content.as_ref()) {
+                        for l in range {
+                            self.render_section(
+                                &self.context,
+                                &Scope {
+                                    context_line: Some(l),
+                                    ..*scope
+                                },
+                                severities,
+                                out,
+                            )
+                        }
+                    }
+                }
+                Field::CtxLine => {
+                    if let Some((ln, _)) = scope.context_line {
+                        out.push_str(&ln.to_string())
+                    }
+                }
+                Field::CtxText => {
+                    if let Some((_, l)) = scope.context_line {
+                        out.push_str(l.trim_end_matches(['\n', '\r']))
+                    }
+                }
+                _ => {
+                    let Some(c) = crumb else {
+                        // crumb fields in file sections are empty
+                        continue;
+                    };
+                    match field {
+                        Field::Line => out.push_str(&c.line_num.to_string()),
+                        Field::Col => out.push_str(&(c.position + 1).to_string()),
+                        Field::EndLine => {
+                            out.push_str(&c.all_lines_num().last().unwrap().to_string())
+                        }
+                        Field::Keyword => out.push_str(c.keyword.as_deref().unwrap_or_default()),
+                        Field::Text => out.push_str(&c.view_content),
+                        Field::Content => out.push_str(&c.content),
+                        Field::Ignored => out.push_str(&c.is_ignore().to_string()),
+                        Field::Tails => out.push_str(
+                            &c.all_lines_num()[1..]
+                                .iter()
+                                .map(|l| l.to_string())
+                                .collect::<Vec<_>>()
+                                .join(" "),
+                        ),
+                        Field::Severity => {
+                            out.push_str(severities.get(c.keyword.as_deref()).as_str())
+                        }
+                        Field::Author | Field::Email | Field::Commit | Field::Date => {
+                            if let Some(b) = &c.blame {
+                                out.push_str(&match field {
+                                    Field::Author => b.author.clone(),
+                                    Field::Email => b.email.clone(),
+                                    Field::Commit => b.commit.clone(),
+                                    _ => b.date(),
+                                })
+                            }
+                        }
+                        _ => unreachable!(),
+                    }
+                }
+            }
+        }
+    }
+
+    /// render the header section
+    pub fn render_header(&self) -> String {
+        self.render_document_section(&self.header)
+    }
+
+    /// render the footer section
+    pub fn render_footer(&self) -> String {
+        self.render_document_section(&self.footer)
+    }
+
+    /// header and footer don't have any bread, only text inside
+    fn render_document_section(&self, section: &Section) -> String {
+        section
+            .0
+            .iter()
+            .filter_map(|p| match p {
+                Piece::Text(t) => Some(t.as_str()),
+                Piece::Field(_) => None,
+            })
+            .collect()
+    }
+
+    /// render the file header, crumbs and file footer of this bread
+    pub fn render_bread(&self, bread: &Bread, severities: &SeverityTable) -> String {
+        let mut out = String::new();
+        let scope = Scope {
+            bread,
+            crumb: None,
+            context_line: None,
+        };
+        self.render_section(&self.file_header, &scope, severities, &mut out);
+        for c in &bread.crumbs {
+            self.render_section(
+                &self.crumb,
+                &Scope {
+                    crumb: Some(c),
+                    ..scope
+                },
+                severities,
+                &mut out,
+            );
+        }
+        self.render_section(&self.file_footer, &scope, severities, &mut out);
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_section() {
+        assert_eq!(
+            "{path}:{line} {{a}}".parse::<Section>().unwrap(),
+            Section(vec![
+                Piece::Field(Field::Path),
+                Piece::Text(":".to_string()),
+                Piece::Field(Field::Line),
+                Piece::Text(" {a}".to_string()),
+            ])
+        );
+
+        assert!("{path".parse::<Section>().is_err());
+        assert!("{owner}".parse::<Section>().is_err());
+        assert!("a}".parse::<Section>().is_err());
+    }
+
+    #[test]
+    fn test_inline_template() {
+        let t = Template::inline("{path}:{line}:{col} [{keyword}] {text}").unwrap();
+        let mut c = Crumb::new(3, 4, "TODO: a".to_string(), "//".to_string());
+        c.keyword = Some("TODO".to_string());
+        c.view_content = "a".to_string();
+        let b = Bread::new("a.rs".to_string(), vec![c]);
+
+        assert_eq!(t.render_header(), "");
+        assert_eq!(
+            t.render_bread(&b, &Default::default()),
+            "a.rs:3:5 [TODO] a\n"
+        );
+    }
+
+    #[test]
+    fn test_template_file() {
+        let t = Template::from_file_content(
+            "[[header]]
+# crumbs
+[[file_header]]
+## {path} ({count})
+[[crumb]]
+- {line} {severity}: {text}
+{context}\\
+[[context]]
+    {ctx_line}| {ctx_text}
+[[footer]]
+end
+",
+        )
+        .unwrap();
+
+        let mut c = Crumb::new(2, 0, "TODO: a".to_string(), "//".to_string());
+        c.keyword = Some("TODO".to_string());
+        c.range_content = Some(vec![(1, "fn a() {\n".to_string()), (2, "//:= a\n".to_string())]);
+        let b = Bread::new(
+            "a.rs".to_string(),
+            vec![c, Crumb::new(5, 0, "b".to_string(), "//".to_string())],
+        );
+
+        assert_eq!(t.render_header(), "# crumbs\n");
+        assert_eq!(
+            t.render_bread(&b, &Default::default()),
+            "## a.rs (2)
+- 2 warning: TODO: a
+    1| fn a() {
+    2| //:= a
+- 5 info: b
+"
+        );
+        assert_eq!(t.render_footer(), "end\n");
+
+        assert!(Template::from_file_content("aaa\n[[crumb]]\n{line}").is_err());
+        assert!(Template::from_file_content("[[crumbs]]\n{line}").is_err());
+        assert!(
+            Template::from_file_content("[[crumb]]\n{context}\n[[context]]\n{line} {context}")
+                .is_err()
+        );
+        assert!(Template::from_file_content("[[file_header]]\n{context}").is_err());
+    }
+}
@@ -0,0 +1,682 @@
+//! The full-screen terminal UI for triaging crumbs
+//!
+//! The crumbs are listed as the tree of files, the crumbs are marked as delete
+//! or restore there, and all marked edits are applied after the confirmation.
+
+use super::config::Config;
+use super::datatypes::*;
+use super::fs_operation;
+use super::notebook;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+use std::process::Command;
+
+const LIST_HELP: &str = "↑↓/jk move  enter fold  space select  d delete  r restore  u unmark  / filter  o open  q finish";
+const FILTER_HELP: &str = "type to filter  enter keep  esc clear";
+const CONFIRM_HELP: &str = "y apply and quit  n quit without applying  esc back";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Row {
+    File(usize),
+    /// bread index and crumb index
+    Crumb(usize, usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Action {
+    Delete,
+    Restore,
+}
+
+#[derive(Debug, PartialEq)]
+enum Screen {
+    List,
+    Confirm,
+}
+
+/// what the event loop should do after the key
+#[derive(Debug, PartialEq)]
+enum Control {
+    Continue,
+    Quit,
+    Apply,
+    /// open the file of bread at this line in editor
+    Open(usize, usize),
+}
+
+struct App {
+    breads: Vec<Bread>,
+    collapsed: HashSet<usize>,
+    filter: String,
+    filtering: bool,
+    /// the index of rows
+    cursor: usize,
+    selected: HashSet<(usize, usize)>,
+    pending: BTreeMap<(usize, usize), Action>,
+    screen: Screen,
+    /// the lines of files (or cells of notebook) for the preview
+    contents: HashMap<(usize, Option<usize>), Vec<String>>,
+}
+
+impl App {
+    fn new(breads: Vec<Bread>) -> Self {
+        Self {
+            breads,
+            collapsed: HashSet::new(),
+            filter: String::new(),
+            filtering: false,
+            cursor: 0,
+            selected: HashSet::new(),
+            pending: BTreeMap::new(),
+            screen: Screen::List,
+            contents: HashMap::new(),
+        }
+    }
+
+    fn matches(&self, b: usize, c: usize) -> bool {
+        if self.filter.is_empty() {
+            return true;
+        }
+        let (bread, crumb) = (&self.breads[b], &self.breads[b].crumbs[c]);
+        format!(
+            "{} {} {}",
+            bread.file_path,
+            crumb.keyword.as_deref().unwrap_or_default(),
+            crumb.view_content
+        )
+        .to_lowercase()
+        .contains(&self.filter.to_lowercase())
+    }
+
+    /// the visible rows, files without matched crumbs are hidden
+    fn rows(&self) -> Vec<Row> {
+        let mut rows = vec![];
+        for (b, bread) in self.breads.iter().enumerate() {
+            let crumbs = (0..bread.crumbs.len())
+                .filter(|c| self.matches(b, *c))
+                .collect::<Vec<_>>();
+            if crumbs.is_empty() {
+                continue;
+            }
+            rows.push(Row::File(b));
+            if !self.collapsed.contains(&b) {
+                rows.extend(crumbs.into_iter().map(|c| Row::Crumb(b, c)))
+            }
+        }
+        rows
+    }
+
+    fn current(&self) -> Option<Row> {
+        self.rows().get(self.cursor).copied()
+    }
+
+    /// the crumbs of the row, the file row means all its visible crumbs
+    fn crumbs_of(&self, row: Row) -> Vec<(usize, usize)> {
+        match row {
+            Row::File(b) => (0..self.breads[b].crumbs.len())
+                .filter(|c| self.matches(b, *c))
+                .map(|c| (b, c))
+                .collect(),
+            Row::Crumb(b, c) => vec![(b, c)],
+        }
+    }
+
+    /// the selected crumbs, or the crumbs of current row if nothing selected
+    fn targets(&self) -> Vec<(usize, usize)> {
+        if !self.selected.is_empty() {
+            return self.selected.iter().copied().collect();
+        }
+        self.current()
+            .map(|r| self.crumbs_of(r))
+            .unwrap_or_default()
+    }
+
+    fn move_cursor(&mut self, delta: isize) {
+        let len = self.rows().len();
+        self.cursor = self
+            .cursor
+            .saturating_add_signed(delta)
+            .min(len.saturating_sub(1));
+    }
+
+    fn toggle_select(&mut self) {
+        let Some(row) = self.current() else { return };
+        let crumbs = self.crumbs_of(row);
+        if crumbs.iter().all(|c| self.selected.contains(c)) {
+            crumbs.iter().for_each(|c| {
+                self.selected.remove(c);
+            })
+        } else {
+            self.selected.extend(crumbs)
+        }
+    }
+
+    fn mark(&mut self, action: Option<Action>) {
+        for t in self.targets() {
+            match action {
+                Some(a) => self.pending.insert(t, a),
+                None => self.pending.remove(&t),
+            };
+        }
+        self.selected.clear()
+    }
+
+    fn toggle_fold(&mut self) {
+        let Some(Row::File(b)) = self.current() else {
+            return;
+        };
+        if !self.collapsed.remove(&b) {
+            self.collapsed.insert(b);
+        }
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) -> Control {
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            return Control::Quit;
+        }
+
+        match self.screen {
+            Screen::Confirm => match key.code {
+                KeyCode::Char('y') => return Control::Apply,
+                KeyCode::Char('n') => return Control::Quit,
+                KeyCode::Esc => self.screen = Screen::List,
+                _ => (),
+            },
+            Screen::List if self.filtering => {
+                match key.code {
+                    KeyCode::Char(ch) => self.filter.push(ch),
+                    KeyCode::Backspace => {
+                        self.filter.pop();
+                    }
+                    KeyCode::Enter => self.filtering = false,
+                    KeyCode::Esc => {
+                        self.filter.clear();
+                        self.filtering = false
+                    }
+                    _ => (),
+                }
+                self.move_cursor(0)
+            }
+            Screen::List => match key.code {
+                KeyCode::Up | KeyCode::Char('k') => self.move_cursor(-1),
+                KeyCode::Down | KeyCode::Char('j') => self.move_cursor(1),
+                KeyCode::PageUp => self.move_cursor(-10),
+                KeyCode::PageDown => self.move_cursor(10),
+                KeyCode::Home => self.cursor = 0,
+                KeyCode::End => self.move_cursor(isize::MAX),
+                KeyCode::Enter | KeyCode::Tab => self.toggle_fold(),
+                KeyCode::Char(' ') => {
+                    self.toggle_select();
+                    self.move_cursor(1)
+                }
+                KeyCode::Char('d') => self.mark(Some(Action::Delete)),
+                KeyCode::Char('r') => self.mark(Some(Action::Restore)),
+                KeyCode::Char('u') => self.mark(None),
+                KeyCode::Char('/') => self.filtering = true,
+                KeyCode::Char('o') => {
+                    if let Some(row) = self.current()
+                        && let Some(&(b, c)) = self.crumbs_of(row).first()
+                    {
+                        return Control::Open(b, self.breads[b].crumbs[c].line_num);
+                    }
+                }
+                KeyCode::Char('q') | KeyCode::Esc => {
+                    if self.pending.is_empty() {
+                        return Control::Quit;
+                    }
+                    self.screen = Screen::Confirm
+                }
+                _ => (),
+            },
+        }
+        Control::Continue
+    }
+
+    /// replace the bread after it changed outside, like in editor. The marks
+    /// of it are dropped because the line numbers may be different
+    fn reload(&mut self, b: usize, new: Option<Bread>) {
+        let path = self.breads[b].file_path.clone();
+        self.breads[b] = new.unwrap_or(Bread::new(path, vec![]));
+        self.pending.retain(|(pb, _), _| *pb != b);
+        self.selected.retain(|(sb, _)| *sb != b);
+        self.contents.retain(|(cb, _), _| *cb != b);
+        self.move_cursor(0)
+    }
+
+    /// the pending edits by files, restore first because it doesn't change
+    /// the line numbers of the crumbs going to be deleted
+    fn pending_by_bread(&self) -> BTreeMap<usize, (HashSet<usize>, HashSet<usize>)> {
+        let mut result: BTreeMap<usize, (HashSet<usize>, HashSet<usize>)> = BTreeMap::new();
+        for (&(b, c), action) in &self.pending {
+            let entry = result.entry(b).or_default();
+            match action {
+                Action::Restore => entry.0.insert(c),
+                Action::Delete => entry.1.insert(c),
+            };
+        }
+        result
+    }
+
+    fn summary(&self) -> Vec<Line<'static>> {
+        let mut lines = vec![];
+        for (b, (restores, deletes)) in self.pending_by_bread() {
+            let bread = &self.breads[b];
+            lines.push(Line::styled(
+                format!(
+                    "{}: delete {}, restore {}",
+                    bread.file_path,
+                    deletes.len(),
+                    restores.len()
+                ),
+                Style::new().add_modifier(Modifier::BOLD),
+            ));
+            for (c, crumb) in bread.crumbs.iter().enumerate() {
+                let (tag, color) = if deletes.contains(&c) {
+                    ("delete ", Color::Red)
+                } else if restores.contains(&c) {
+                    ("restore", Color::Yellow)
+                } else {
+                    continue;
+                };
+                lines.push(Line::from(vec![
+                    Span::styled(format!("  {} ", tag), Style::new().fg(color)),
+                    Span::raw(format!("Line {}: {}", crumb.line_num, crumb_title(crumb))),
+                ]));
+            }
+        }
+        lines
+    }
+
+    /// apply all pending edits, return the changed files
+    fn apply(&self) -> Result<HashSet<String>, String> {
+        let mut files_changed = HashSet::new();
+        for (b, (restores, deletes)) in self.pending_by_bread() {
+            let bread = &self.breads[b];
+            if !restores.is_empty() {
+                files_changed.insert(
+                    fs_operation::restore_crumbs_on_indexes(bread.clone(), &restores)
+                        .map_err(|e| e.to_string())?,
+                );
+            }
+            if !deletes.is_empty() {
+                files_changed.insert(
+                    fs_operation::delete_crumbs_on_indexes(bread.clone(), &deletes)
+                        .map_err(|e| e.to_string())?,
+                );
+            }
+        }
+        Ok(files_changed)
+    }
+
+    /// the context lines around the current crumb, the crumb lines highlighted
+    fn preview(&mut self, height: usize) -> Vec<Line<'static>> {
+        let Some(&(b, c)) = self
+            .current()
+            .map(|r| self.crumbs_of(r))
+            .unwrap_or_default()
+            .first()
+        else {
+            return vec![];
+        };
+        let file_path = self.breads[b].file_path.clone();
+        let cell = self.breads[b].crumbs[c].cell;
+        let contents = self.contents.entry((b, cell)).or_insert_with(|| {
+            notebook::read_source(&file_path, cell)
+                .map(|s| s.lines().map(String::from).collect())
+                .unwrap_or_default()
+        });
+
+        let crumb_lines = self.breads[b].crumbs[c].all_lines_num();
+        let first = crumb_lines[0].saturating_sub(height / 2).max(1);
+        contents
+            .iter()
+            .enumerate()
+            .skip(first - 1)
+            .take(height)
+            .map(|(i, l)| {
+                let text = format!("{:>5} │ {}", i + 1, l);
+                if crumb_lines.contains(&(i + 1)) {
+                    Line::styled(
+                        text,
+                        Style::new().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+                    )
+                } else {
+                    Line::raw(text)
+                }
+            })
+            .collect()
+    }
+}
+
+fn crumb_title(crumb: &Crumb) -> String {
+    match &crumb.keyword {
+        Some(k) => format!("{}: {}", k, crumb.view_content),
+        None => crumb.view_content.clone(),
+    }
+}
+
+fn draw(frame: &mut Frame, app: &mut App) {
+    let [top, main, bottom] = Layout::vertical([
+        Constraint::Length(1),
+        Constraint::Min(0),
+        Constraint::Length(1),
+    ])
+    .areas(frame.area());
+
+    let filter = if app.filtering {
+        format!("filter: {}_", app.filter)
+    } else if !app.filter.is_empty() {
+        format!("filter: {}", app.filter)
+    } else {
+        String::new()
+    };
+    frame.render_widget(
+        Line::from(format!(
+            "codeitlater  {} pending, {} selected  {}",
+            app.pending.len(),
+            app.selected.len(),
+            filter
+        ))
+        .style(Style::new().add_modifier(Modifier::REVERSED)),
+        top,
+    );
+
+    let help = match app.screen {
+        Screen::Confirm => CONFIRM_HELP,
+        Screen::List if app.filtering => FILTER_HELP,
+        Screen::List => LIST_HELP,
+    };
+    frame.render_widget(Line::raw(help), bottom);
+
+    if app.screen == Screen::Confirm {
+        frame.render_widget(
+            Paragraph::new(app.summary()).block(Block::bordered().title("Pending edits")),
+            main,
+        );
+        return;
+    }
+
+    let [left, right] =
+        Layout::horizontal([Constraint::Percentage(45), Constraint::Percentage(55)]).areas(main);
+    let items = app
+        .rows()
+        .into_iter()
+        .map(|row| match row {
+            Row::File(b) => {
+                let fold = if app.collapsed.contains(&b) {
+                    "▸"
+                } else {
+                    "▾"
+                };
+                ListItem::new(format!(
+                    "{} {} ({})",
+                    fold,
+                    app.breads[b].file_path,
+                    app.crumbs_of(row).len()
+                ))
+                .style(Style::new().add_modifier(Modifier::BOLD))
+            }
+            Row::Crumb(b, c) => {
+                let crumb = &app.breads[b].crumbs[c];
+                let (mark, style) = match app.pending.get(&(b, c)) {
+                    Some(Action::Delete) => ("D", Style::new().fg(Color::Red)),
+                    Some(Action::Restore) => ("R", Style::new().fg(Color::Yellow)),
+                    None => (" ", Style::new()),
+                };
+                let sel = if app.selected.contains(&(b, c)) {
+                    "*"
+                } else {
+                    " "
+                };
+                ListItem::new(format!(
+                    "  {}{} L{} {}",
+                    sel,
+                    mark,
+                    crumb.line_num,
+                    crumb_title(crumb)
+                ))
+                .style(style)
+            }
+        })
+        .collect::<Vec<_>>();
+    let mut state = ListState::default().with_selected(Some(app.cursor));
+    frame.render_stateful_widget(
+        List::new(items)
+            .block(Block::bordered().title("Crumbs"))
+            .highlight_style(Style::new().add_modifier(Modifier::REVERSED)),
+        left,
+        &mut state,
+    );
+
+    let preview = app.preview(right.height.saturating_sub(2) as usize);
+    frame.render_widget(
+        Paragraph::new(preview).block(Block::bordered().title("Context")),
+        right,
+    );
+}
+
+/// open the file in `$VISUAL` or `$EDITOR` (vi by default) at the line
+fn open_in_editor(file_path: &str, line: usize) -> Result<(), String> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or("vi".to_string());
+    let mut editor = editor.split_whitespace();
+    Command::new(editor.next().ok_or("editor is empty")?)
+        .args(editor)
+        .arg(format!("+{}", line))
+        .arg(file_path)
+        .status()
+        .map_err(|e| format!("cannot open editor: {}", e))?;
+    Ok(())
+}
+
+fn event_loop(
+    terminal: &mut DefaultTerminal,
+    app: &mut App,
+    conf: &Config,
+) -> Result<Control, String> {
+    loop {
+        terminal.draw(|f| draw(f, app)).map_err(|e| e.to_string())?;
+        let Event::Key(key) = event::read().map_err(|e| e.to_string())? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        match app.handle_key(key) {
+            Control::Continue => (),
+            Control::Open(b, line) => {
+                ratatui::restore();
+                let opened = open_in_editor(&app.breads[b].file_path, line);
+                *terminal = ratatui::init();
+                opened?;
+                let new = fs_operation::bake_file(Path::new(&app.breads[b].file_path), conf);
+                app.reload(b, new)
+            }
+            c => return Ok(c),
+        }
+    }
+}
+
+/// scan the targets and run the terminal UI, return the changed files
+pub fn run(mut conf: Config) -> Result<Option<HashSet<String>>, String> {
+    conf.delete = false;
+    conf.restore = false;
+    let mut app = App::new(fs_operation::handle_files(conf.clone()).collect());
+
+    let mut terminal = ratatui::init();
+    let control = event_loop(&mut terminal, &mut app, &conf);
+    ratatui::restore();
+
+    match control? {
+        Control::Apply => {
+            let files_changed = app.apply()?;
+            for f in &files_changed {
+                println!("changed the crumbs in {}", f)
+            }
+            Ok(Some(files_changed).filter(|f| !f.is_empty()))
+        }
+        _ => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::{Terminal, backend::TestBackend};
+    use std::fs;
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    fn test_app() -> App {
+        let crumb =
+            |line, content: &str| Crumb::new(line, 0, content.to_string(), "//".to_string());
+        App::new(vec![
+            Bread::new(
+                "a.rs".to_string(),
+                vec![crumb(1, "TODO: one"), crumb(5, "FIXME: two")],
+            ),
+            Bread::new("b.rs".to_string(), vec![crumb(2, "TODO: three")]),
+        ])
+    }
+
+    #[test]
+    fn test_rows_and_filter() {
+        let mut app = test_app();
+        assert_eq!(app.rows().len(), 5);
+
+        app.handle_key(key(KeyCode::Enter));
+        assert_eq!(
+            app.rows(),
+            vec![Row::File(0), Row::File(1), Row::Crumb(1, 0)]
+        );
+        app.handle_key(key(KeyCode::Enter));
+
+        for k in [KeyCode::Char('/'), KeyCode::Char('t'), KeyCode::Char('o')] {
+            app.handle_key(key(k));
+        }
+        assert_eq!(
+            app.rows(),
+            vec![
+                Row::File(0),
+                Row::Crumb(0, 0),
+                Row::File(1),
+                Row::Crumb(1, 0)
+            ]
+        );
+        // keys are the filter when filtering
+        app.handle_key(key(KeyCode::Char('q')));
+        assert_eq!(app.filter, "toq");
+        assert!(app.rows().is_empty());
+
+        app.handle_key(key(KeyCode::Esc));
+        assert!(!app.filtering);
+        assert_eq!(app.rows().len(), 5);
+    }
+
+    #[test]
+    fn test_mark_and_confirm() {
+        let mut app = test_app();
+        // mark the whole file a.rs as restore, then delete the second crumb
+        app.handle_key(key(KeyCode::Char('r')));
+        app.handle_key(key(KeyCode::Down));
+        app.handle_key(key(KeyCode::Down));
+        app.handle_key(key(KeyCode::Char('d')));
+        assert_eq!(
+            app.pending_by_bread()[&0],
+            (HashSet::from([0]), HashSet::from([1]))
+        );
+
+        // select and unmark
+        app.handle_key(key(KeyCode::Up));
+        app.handle_key(key(KeyCode::Char(' ')));
+        app.handle_key(key(KeyCode::Char(' ')));
+        assert_eq!(app.selected.len(), 2);
+        app.handle_key(key(KeyCode::Char('u')));
+        assert!(app.pending.is_empty());
+        assert!(app.selected.is_empty());
+
+        assert_eq!(app.handle_key(key(KeyCode::Char('o'))), Control::Open(1, 2));
+
+        app.handle_key(key(KeyCode::Char('d')));
+        assert_eq!(app.handle_key(key(KeyCode::Char('q'))), Control::Continue);
+        assert_eq!(app.screen, Screen::Confirm);
+        assert_eq!(app.summary().len(), 2);
+        app.handle_key(key(KeyCode::Esc));
+        assert_eq!(app.screen, Screen::List);
+        app.handle_key(key(KeyCode::Char('q')));
+        assert_eq!(app.handle_key(key(KeyCode::Char('y'))), Control::Apply);
+
+        app.handle_key(key(KeyCode::Esc));
+        app.reload(1, None);
+        assert!(app.pending.is_empty());
+        assert_eq!(app.handle_key(key(KeyCode::Char('q'))), Control::Quit);
+    }
+
+    #[test]
+    fn test_apply() {
+        let path = std::env::temp_dir().join(format!("codeitlater-tui-{}.rs", std::process::id()));
+        fs::write(&path, "a //:= one\n//:= two\nb //:= three\n").unwrap();
+        let crumb =
+            |line, pos, content: &str| Crumb::new(line, pos, content.to_string(), "//".to_string());
+        let mut app = App::new(vec![Bread::new(
+            path.to_str().unwrap().to_string(),
+            vec![crumb(1, 2, "one"), crumb(2, 0, "two"), crumb(3, 2, "three")],
+        )]);
+        app.pending.insert((0, 0), Action::Restore);
+        app.pending.insert((0, 1), Action::Delete);
+        app.pending.insert((0, 2), Action::Restore);
+
+        assert_eq!(app.apply().unwrap().len(), 1);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "a // one\nb // three\n");
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_preview_notebook() {
+        let crumb = Crumb::new(2, 0, "FIXME: a".to_string(), "#".to_string()).in_cell(2);
+        let mut app = App::new(vec![Bread::new(
+            "./tests/testcases/notebook.ipynb".to_string(),
+            vec![crumb],
+        )]);
+        let preview = app
+            .preview(3)
+            .iter()
+            .map(|l| l.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            preview,
+            vec![
+                "    1 │ import os  #:= TODO: fix it",
+                "    2 │ #:= FIXME: a...",
+                "    3 │ #:= b"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_draw() {
+        let mut app = test_app();
+        app.handle_key(key(KeyCode::Char('d')));
+        let mut terminal = Terminal::new(TestBackend::new(80, 10)).unwrap();
+        terminal.draw(|f| draw(f, &mut app)).unwrap();
+        let screen = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|c| c.symbol())
+            .collect::<String>();
+        assert!(screen.contains("2 pending"));
+        assert!(screen.contains("▾ a.rs (2)"));
+        assert!(screen.contains(" D L5 FIXME: two"));
+    }
+}
@@ -0,0 +1,240 @@
+//! Watch the targets and re-scan the changed files
+
+use super::config::{Config, OutputFormat};
+use super::datatypes::*;
+use super::fs_operation;
+use super::output::{JsonlRecord, to_jsonl_records};
+use notify::{RecursiveMode, Watcher};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::path::{Component, Path, PathBuf};
+use std::sync::mpsc;
+use std::time::Duration;
+
+/// wait this long for more events after one event, so the editor saving
+/// one file several times only makes one re-scan
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// the event of jsonl output in watch mode
+#[derive(Debug, Serialize)]
+pub struct WatchEvent {
+    pub event: WatchEventKind,
+    #[serde(flatten)]
+    pub record: JsonlRecord,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WatchEventKind {
+    Add,
+    Remove,
+}
+
+/// the crumbs only in old (removed) and only in new (added). Crumbs are the
+/// same when they are on the same line with the same content
+fn diff_crumbs<'a>(old: &'a [Crumb], new: &'a [Crumb]) -> (Vec<usize>, Vec<usize>) {
+    let key = |c: &Crumb| (c.line_num, c.view_content.clone(), c.keyword.clone());
+    let old_keys = old.iter().map(key).collect::<HashSet<_>>();
+    let new_keys = new.iter().map(key).collect::<HashSet<_>>();
+
+    (
+        (0..old.len())
+            .filter(|i| !new_keys.contains(&key(&old[*i])))
+            .collect(),
+        (0..new.len())
+            .filter(|i| !old_keys.contains(&key(&new[*i])))
+            .collect(),
+    )
+}
+
+/// the jsonl events of the changes from old bread to new bread
+fn events_of_change(
+    file_path: &str,
+    old: Option<&Bread>,
+    new: Option<&Bread>,
+) -> Vec<WatchEvent> {
+    let empty = Bread::new(file_path.to_string(), vec![]);
+    let (old, new) = (old.unwrap_or(&empty), new.unwrap_or(&empty));
+    let (removed, added) = diff_crumbs(&old.crumbs, &new.crumbs);
+
+    let mut events = vec![];
+    for (ind, record) in to_jsonl_records(old).enumerate() {
+        if removed.contains(&ind) {
+            events.push(WatchEvent {
+                event: WatchEventKind::Remove,
+                record,
+            })
+        }
+    }
+    for (ind, record) in to_jsonl_records(new).enumerate() {
+        if added.contains(&ind) {
+            events.push(WatchEvent {
+                event: WatchEventKind::Add,
+                record,
+            })
+        }
+    }
+    events
+}
+
+/// the key of the path in the breads table, so `./a.rs`, `a.rs`
+/// and `/cwd/a.rs` are the same
+fn normalize(path: &Path) -> PathBuf {
+    let path = match std::env::current_dir() {
+        Ok(cwd) if path.is_relative() => cwd.join(path),
+        _ => path.to_path_buf(),
+    };
+    path.components()
+        .filter(|c| !matches!(c, Component::CurDir))
+        .collect()
+}
+
+fn print_change(
+    conf: &Config,
+    file_path: &str,
+    old: Option<&Bread>,
+    new: Option<&Bread>,
+) -> Result<(), String> {
+    match conf.output {
+        OutputFormat::Jsonl => {
+            for e in events_of_change(file_path, old, new) {
+                println!("{}", serde_json::to_string(&e).map_err(|e| e.to_string())?)
+            }
+        }
+        _ => match new {
+            Some(b) => println!("{}", b),
+            None => println!("|-- {} (no crumbs)\n", file_path),
+        },
+    }
+    Ok(())
+}
+
+/// scan all targets first, then keep watching them and re-scan the changed
+/// files. Print the changed breads again, or the add/remove events in jsonl
+pub fn watch(conf: Config) -> Result<!, String> {
+    let mut breads: HashMap<PathBuf, Bread> = HashMap::new();
+    for b in fs_operation::handle_files(conf.clone()) {
+        print_change(&conf, &b.file_path, None, Some(&b))?;
+        breads.insert(normalize(Path::new(&b.file_path)), b);
+    }
+
+    let (sender, receiver) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(sender).map_err(|e| e.to_string())?;
+    for t in &conf.files {
+        let mode = if Path::new(t).is_dir() {
+            RecursiveMode::Recursive
+        } else {
+            RecursiveMode::NonRecursive
+        };
+        watcher
+            .watch(Path::new(t), mode)
+            .map_err(|e| format!("cannot watch {}: {}", t, e))?;
+    }
+
+    loop {
+        let mut changed = vec![];
+        let mut event = receiver.recv().map_err(|e| e.to_string());
+        loop {
+            match event {
+                Ok(Ok(e)) => e.paths.into_iter().for_each(|p| {
+                    if !changed.contains(&p) {
+                        changed.push(p)
+                    }
+                }),
+                Ok(Err(e)) => log::debug!("watch error {}", e),
+                Err(e) => return Err(e),
+            }
+
+            match receiver.recv_timeout(DEBOUNCE) {
+                Ok(e) => event = Ok(e),
+                Err(mpsc::RecvTimeoutError::Timeout) => break,
+                Err(e) => return Err(e.to_string()),
+            }
+        }
+
+        for path in changed {
+            if path.is_dir() {
+                continue;
+            }
+            let key = normalize(&path);
+            let old = breads.remove(&key);
+            // keep the same file path of the bread scanned before
+            let path = match &old {
+                Some(b) => PathBuf::from(&b.file_path),
+                None => std::env::current_dir()
+                    .ok()
+                    .and_then(|cwd| key.strip_prefix(cwd).ok().map(Path::to_path_buf))
+                    .unwrap_or(path),
+            };
+            let new = fs_operation::bake_file(&path, &conf);
+            if old.is_none() && new.is_none() {
+                continue;
+            }
+            if old == new {
+                // like only touched
+                breads.insert(key, new.unwrap());
+                continue;
+            }
+
+            let file_path = match (&old, &new) {
+                (Some(b), _) | (None, Some(b)) => b.file_path.clone(),
+                (None, None) => unreachable!(),
+            };
+            print_change(&conf, &file_path, old.as_ref(), new.as_ref())?;
+            if let Some(b) = new {
+                breads.insert(key, b);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_events_of_change() {
+        let old = Bread::new(
+            "a.rs".to_string(),
+            vec![
+                Crumb::new(1, 0, "a".to_string(), "//".to_string()),
+                Crumb::new(3, 0, "b".to_string(), "//".to_string()),
+            ],
+        );
+        let new = Bread::new(
+            "a.rs".to_string(),
+            vec![
+                Crumb::new(1, 0, "a".to_string(), "//".to_string()),
+                Crumb::new(4, 0, "b".to_string(), "//".to_string()),
+                Crumb::new(5, 0, "c".to_string(), "//".to_string()),
+            ],
+        );
+
+        let events = events_of_change("a.rs", Some(&old), Some(&new));
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[0].event, WatchEventKind::Remove);
+        assert_eq!(events[0].record.crumb.line_num, 3);
+        assert_eq!(events[1].event, WatchEventKind::Add);
+        assert_eq!(events[1].record.crumb.line_num, 4);
+        assert_eq!(events[2].record.crumb.line_num, 5);
+
+        let value = serde_json::to_value(&events[0]).unwrap();
+        assert_eq!(value["event"], "remove");
+        assert_eq!(value["file_path"], "a.rs");
+        assert_eq!(value["view_content"], "b");
+
+        // file deleted
+        let events = events_of_change("a.rs", Some(&old), None);
+        assert_eq!(events.len(), 2);
+        assert!(events.iter().all(|e| e.event == WatchEventKind::Remove));
+    }
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(normalize(Path::new("./a/b.rs")), normalize(Path::new("a/b.rs")));
+        assert_eq!(
+            normalize(Path::new("a/b.rs")),
+            std::env::current_dir().unwrap().join("a/b.rs")
+        );
+    }
+}
@@ -0,0 +1,36 @@
+//! the helpers shared by the integration tests
+
+// every test file uses only some of them
+#![allow(dead_code)]
+
+use clap::Parser;
+use code_it_later_rs::{args::*, *};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// write the content to the temp file of test. The file name has the test
+/// group and the pid, the name of case should have the file extension
+pub fn case(group: &str, name: &str, content: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!(
+        "codeitlater-{}-{}-{}",
+        group,
+        std::process::id(),
+        name
+    ));
+    fs::write(&path, content).unwrap();
+    path
+}
+
+/// the config of the command line arguments, without the program name
+pub fn conf(args: &[&str]) -> config::Config {
+    let mut all = vec!["codeitlater"];
+    all.extend_from_slice(args);
+    config::Config::from(&Args::parse_from(all))
+}
+
+/// the config of the command line arguments scanning the path
+pub fn conf_on(path: &Path, args: &[&str]) -> config::Config {
+    let mut all = args.to_vec();
+    all.push(path.to_str().unwrap());
+    conf(&all)
+}
@@ -0,0 +1,76 @@
+mod common;
+
+use code_it_later_rs::*;
+use std::fs;
+use std::path::PathBuf;
+
+fn case(name: &str, ext: &str, content: &str) -> PathBuf {
+    common::case("add", &format!("{}.{}", name, ext), content)
+}
+
+fn add(args: &[&str]) -> Result<Option<std::collections::HashSet<String>>, String> {
+    let mut all = vec!["add"];
+    all.extend_from_slice(args);
+    prompt(common::conf(&all))
+}
+
+#[test]
+fn test_add_crumb() {
+    let path = case("rs", "rs", "fn main() {\n    let a = 1;\n}\n");
+    let location = |line: usize| format!("{}:{}", path.display(), line);
+
+    let changed = add(&[&location(2), "TODO: handle a"]).unwrap().unwrap();
+    assert!(changed.contains(path.to_str().unwrap()));
+    add(&["--append", &location(3), "FIXME: b"]).unwrap();
+    add(&[&location(5), "after the last line"]).unwrap();
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        "fn main() {\n    //:= TODO: handle a\n    let a = 1; //:= FIXME: b\n}\n//:= after the last line\n"
+    );
+
+    // the added crumbs can be found
+    let bread = fs_operation::bake_file(&path, &config::Config::default()).unwrap();
+    assert_eq!(
+        output::to_jsonl_records(&bread)
+            .map(|r| (r.crumb.line_num, r.crumb.content))
+            .collect::<Vec<_>>(),
+        vec![
+            (2, "TODO: handle a".to_string()),
+            (3, "FIXME: b".to_string()),
+            (5, "after the last line".to_string())
+        ]
+    );
+
+    assert!(add(&[&location(7), "out of file"]).is_err());
+    assert!(add(&["--append", &location(6), "no line to append"]).is_err());
+    assert!(add(&[&location(1), " "]).is_err());
+    assert!(add(&[&path.display().to_string(), "no line"]).is_err());
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_add_crumb_other_languages() {
+    let path = case("py", "py", "def f():\n    pass");
+    add(&[&format!("{}:2", path.display()), "TODO: python"]).unwrap();
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        "def f():\n    #:= TODO: python\n    pass"
+    );
+    fs::remove_file(&path).unwrap();
+
+    // the line endings are kept, and no newline is added at the end
+    let path = case("crlf", "rs", "fn a() {\r\n    1\r\n}");
+    let location = |line: usize| format!("{}:{}", path.display(), line);
+    add(&[&location(2), "TODO: one"]).unwrap();
+    add(&["--append", &location(4), "the end"]).unwrap();
+    add(&[&location(5), "after"]).unwrap();
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        "fn a() {\r\n    //:= TODO: one\r\n    1\r\n} //:= the end\r\n//:= after"
+    );
+    fs::remove_file(&path).unwrap();
+
+    let path = case("unknown", "unknown", "a\n");
+    assert!(add(&[&format!("{}:1", path.display()), "TODO: x"]).is_err());
+    fs::remove_file(&path).unwrap();
+}
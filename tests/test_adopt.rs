@@ -0,0 +1,78 @@
+mod common;
+
+use code_it_later_rs::*;
+use common::conf_on;
+use std::fs;
+use std::path::PathBuf;
+
+const CONTENT: &str = "fn main() {
+    // TODO: plain one
+    let a = 1; // FIXME handle
+    //:= TODO: crumb
+    // Note: not keyword
+}
+";
+
+fn case(name: &str) -> PathBuf {
+    common::case("adopt", &format!("{}.rs", name), CONTENT)
+}
+
+#[test]
+fn test_compat_scan() {
+    let path = case("scan");
+    let contents = |args: &[&str]| {
+        fs_operation::handle_files(conf_on(&path, args))
+            .flat_map(|b| {
+                output::to_jsonl_records(&b)
+                    .map(|r| (r.crumb.content, r.crumb.plain))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(contents(&[]), vec![("TODO: crumb".to_string(), false)]);
+    assert_eq!(
+        contents(&["--compat"]),
+        vec![
+            ("TODO: plain one".to_string(), true),
+            ("FIXME: handle".to_string(), true),
+            ("TODO: crumb".to_string(), false),
+        ]
+    );
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_adopt() {
+    let path = case("adopt");
+    // restore doesn't touch the plain comments
+    assert_eq!(
+        prompt(conf_on(
+            &path,
+            &["-R", "-y", "--compat", "--select-lines", "1-3"]
+        ))
+        .unwrap(),
+        None
+    );
+
+    let changed = prompt(conf_on(&path, &["-y", "--select-lines", "3", "adopt"])).unwrap();
+    assert!(changed.unwrap().contains(path.to_str().unwrap()));
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        CONTENT.replace("// FIXME handle", "//:= FIXME: handle")
+    );
+
+    // dry run
+    assert_eq!(
+        prompt(conf_on(&path, &["--no-input", "adopt"])).unwrap(),
+        None
+    );
+    prompt(conf_on(&path, &["-y", "adopt"])).unwrap();
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        CONTENT
+            .replace("// FIXME handle", "//:= FIXME: handle")
+            .replace("// TODO: plain", "//:= TODO: plain")
+    );
+    fs::remove_file(&path).unwrap();
+}
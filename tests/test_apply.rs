@@ -0,0 +1,80 @@
+mod common;
+
+use code_it_later_rs::*;
+use common::conf;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const CONTENT: &str = "fn main() {
+    //:= HACK: first
+    b(); //:= HACK: fix...
+    //:= the tail
+    //:= TODO: keep
+}
+";
+
+fn case(name: &str) -> PathBuf {
+    common::case("apply", &format!("{}.rs", name), CONTENT)
+}
+
+/// the json document of the file, edited by `edit`
+fn export(path: &Path, edit: impl Fn(&mut output::CrumbRecord)) -> PathBuf {
+    let breads = fs_operation::handle_files(conf(&["-k", "hack", path.to_str().unwrap()]))
+        .collect::<Vec<_>>();
+    let mut doc = output::JsonDocument::new(&breads);
+    doc.breads
+        .iter_mut()
+        .flat_map(|b| b.crumbs.iter_mut())
+        .for_each(edit);
+
+    let json = path.with_extension("json");
+    fs::write(&json, serde_json::to_string(&doc).unwrap()).unwrap();
+    json
+}
+
+#[test]
+fn test_apply_keyword_rename() {
+    let path = case("rename");
+    let json = export(&path, |c| c.keyword = Some("FIXME".to_string()));
+
+    // dry run
+    assert_eq!(
+        prompt(conf(&["--no-input", "apply", json.to_str().unwrap()])).unwrap(),
+        None
+    );
+    assert_eq!(fs::read_to_string(&path).unwrap(), CONTENT);
+
+    let changed = prompt(conf(&["apply", json.to_str().unwrap()]))
+        .unwrap()
+        .unwrap();
+    assert!(changed.contains(path.to_str().unwrap()));
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        CONTENT.replace("HACK", "FIXME")
+    );
+
+    // the crumbs in document are changed
+    assert!(prompt(conf(&["apply", json.to_str().unwrap()])).is_err());
+    fs::remove_file(&path).unwrap();
+    fs::remove_file(&json).unwrap();
+}
+
+#[test]
+fn test_apply_rewrite_and_remove() {
+    let path = case("rewrite");
+    let json = export(&path, |c| {
+        if c.line_num == 2 {
+            c.remove = true
+        } else {
+            c.view_content = "one line".to_string()
+        }
+    });
+
+    prompt(conf(&["apply", json.to_str().unwrap()])).unwrap();
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        "fn main() {\n    \n    b(); //:= HACK: one line\n    //:= the tail\n    //:= TODO: keep\n}\n"
+    );
+    fs::remove_file(&path).unwrap();
+    fs::remove_file(&json).unwrap();
+}
@@ -0,0 +1,72 @@
+use clap::Parser;
+use code_it_later_rs::{args::*, *};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+fn git(dir: &Path, args: &[&str], date: &str) {
+    let status = Command::new("git")
+        .current_dir(dir)
+        .args(["-c", "user.name=Alice", "-c", "user.email=alice@example.com"])
+        .args(args)
+        .env("GIT_AUTHOR_DATE", date)
+        .env("GIT_COMMITTER_DATE", date)
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+/// make a git repo, the old.rs is committed in 2001, the new.rs is untracked
+fn make_repo() -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("codeitlater_blame_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+
+    git(&dir, &["init", "-q"], "2001-01-01T00:00:00Z");
+    fs::write(dir.join("old.rs"), "//:= old crumb...\n//:= tail\nfn a() {}\n").unwrap();
+    git(&dir, &["add", "old.rs"], "2001-01-01T00:00:00Z");
+    git(&dir, &["commit", "-q", "-m", "old"], "2001-01-01T00:00:00Z");
+    fs::write(dir.join("new.rs"), "//:= new crumb\n").unwrap();
+    dir
+}
+
+#[test]
+fn test_blame() {
+    let dir = make_repo();
+    let target = dir.to_str().unwrap();
+
+    let args = Args::parse_from(vec!["codeitlater", "--blame", "--sort", "age", target]);
+    let breads = fs_operation::handle_files(config::Config::from(&args)).collect::<Vec<_>>();
+    assert_eq!(breads.len(), 2);
+
+    // old one first
+    let records = breads
+        .iter()
+        .map(|b| serde_json::to_value(output::BreadRecord::from(b)).unwrap())
+        .collect::<Vec<_>>();
+    let old = &records[0]["crumbs"][0];
+    assert_eq!(old["view_content"], "old crumb tail");
+    assert_eq!(old["blame"]["author"], "Alice");
+    assert_eq!(old["blame"]["email"], "alice@example.com");
+    assert_eq!(old["blame"]["timestamp"], 978307200);
+    assert_eq!(old["tail_blames"][0]["author"], "Alice");
+    // untracked file doesn't have blame
+    assert!(records[1]["crumbs"][0].get("blame").is_none());
+
+    let args = Args::parse_from(vec!["codeitlater", "--older-than", "365d", target]);
+    let breads = fs_operation::handle_files(config::Config::from(&args)).collect::<Vec<_>>();
+    assert_eq!(breads.len(), 1);
+    assert!(breads[0].to_string().contains("old crumb tail (Alice, 2001-01-01)"));
+
+    let args = Args::parse_from(vec!["codeitlater", "--author", "ALICE@example", target]);
+    let breads = fs_operation::handle_files(config::Config::from(&args)).collect::<Vec<_>>();
+    assert_eq!(breads.len(), 1);
+
+    let args = Args::parse_from(vec!["codeitlater", "--author", "bob", target]);
+    assert_eq!(
+        fs_operation::handle_files(config::Config::from(&args)).count(),
+        0
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
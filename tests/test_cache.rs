@@ -0,0 +1,60 @@
+use clap::Parser;
+use code_it_later_rs::{args::*, *};
+use std::fs;
+
+fn scan(args: &Args) -> Vec<String> {
+    let mut result = fs_operation::handle_files(config::Config::from(args))
+        .flat_map(|b| {
+            output::to_jsonl_records(&b)
+                .map(|r| {
+                    let r = serde_json::to_value(r).unwrap();
+                    format!("{}:{}:{}", r["file_path"], r["line_num"], r["content"])
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    result.sort();
+    result
+}
+
+#[test]
+fn test_cache() {
+    let dir = std::env::temp_dir().join(format!("codeitlater-cache-{}", std::process::id()));
+    let src = dir.join("src");
+    fs::create_dir_all(&src).unwrap();
+    let cache_file = dir.join("cache.json");
+    fs::write(src.join("a.rs"), "//:= one\nfn a() {}\n").unwrap();
+    fs::write(src.join("b.rs"), "//:= two\n").unwrap();
+
+    let args = Args::parse_from(vec![
+        "codeitlater",
+        "--cache",
+        cache_file.to_str().unwrap(),
+        src.to_str().unwrap(),
+    ]);
+
+    let first = scan(&args);
+    assert_eq!(first.len(), 2);
+    assert!(cache_file.exists());
+
+    // same result from cache
+    assert_eq!(scan(&args), first);
+
+    // changed file is parsed again
+    fs::write(src.join("a.rs"), "//:= one\n//:= three\n").unwrap();
+    let third = scan(&args);
+    assert_eq!(third.len(), 3);
+    assert!(third.iter().any(|s| s.ends_with(":2:\"three\"")));
+
+    // removed file is dropped from cache
+    fs::remove_file(src.join("b.rs")).unwrap();
+    assert_eq!(scan(&args).len(), 2);
+    let cache_content = fs::read_to_string(&cache_file).unwrap();
+    assert!(!cache_content.contains("b.rs"));
+
+    // broken cache file is ignored
+    fs::write(&cache_file, "not json").unwrap();
+    assert_eq!(scan(&args).len(), 2);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
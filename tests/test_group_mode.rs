@@ -0,0 +1,77 @@
+mod common;
+
+use code_it_later_rs::*;
+use std::fs;
+
+const CONTENT: &str = "//:= TODO: explicit...
+//:= continued...
+//:= swallowed...
+//:= MARK: new keyword...
+/*:= other header
+fn a() {} //:= trailing...
+//:= not aligned
+
+//:= TODO: indented
+//:=     more indented
+//:= same indent
+";
+
+/// the line number and tail lines of all crumbs in the group mode
+fn groups(mode: &str) -> Vec<(usize, Vec<usize>)> {
+    let path = common::case("group-mode", &format!("{}.rs", mode), CONTENT);
+    let conf = common::conf_on(&path, &["--group-mode", mode]);
+    let result = fs_operation::handle_files(conf)
+        .flat_map(|b| output::to_jsonl_records(&b).collect::<Vec<_>>())
+        .map(|r| (r.crumb.line_num, r.crumb.tail_lines))
+        .collect();
+    fs::remove_file(path).unwrap();
+    result
+}
+
+#[test]
+fn test_group_mode_explicit() {
+    assert_eq!(
+        groups("explicit"),
+        vec![
+            (1, vec![2, 3]),
+            (4, vec![]),
+            (5, vec![]),
+            (6, vec![]),
+            (7, vec![]),
+            (9, vec![]),
+            (10, vec![]),
+            (11, vec![])
+        ]
+    );
+}
+
+#[test]
+fn test_group_mode_consecutive() {
+    assert_eq!(
+        groups("consecutive"),
+        vec![
+            (1, vec![2, 3]),
+            (4, vec![]),
+            (5, vec![]),
+            (6, vec![]),
+            (7, vec![]),
+            (9, vec![10, 11])
+        ]
+    );
+}
+
+#[test]
+fn test_group_mode_indent() {
+    assert_eq!(
+        groups("indent"),
+        vec![
+            (1, vec![2, 3]),
+            (4, vec![]),
+            (5, vec![]),
+            (6, vec![]),
+            (7, vec![]),
+            (9, vec![10]),
+            (11, vec![])
+        ]
+    );
+}
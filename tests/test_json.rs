@@ -0,0 +1,42 @@
+use clap::Parser;
+use code_it_later_rs::{args::*, *};
+
+#[test]
+fn test_stream_files_same_as_handle_files() {
+    let args = Args::parse_from(vec!["codeitlater", "./tests/testcases"]);
+
+    let mut handled = fs_operation::handle_files(config::Config::from(&args))
+        .map(|b| serde_json::to_string(&output::BreadRecord::from(&b)).unwrap())
+        .collect::<Vec<_>>();
+    let mut streamed = fs_operation::stream_files(config::Config::from(&args))
+        .map(|b| serde_json::to_string(&output::BreadRecord::from(&b)).unwrap())
+        .collect::<Vec<_>>();
+
+    assert!(!handled.is_empty());
+    handled.sort();
+    streamed.sort();
+    assert_eq!(handled, streamed);
+}
+
+#[test]
+fn test_jsonl_records() {
+    let args = Args::parse_from(vec!["codeitlater", "./tests/testcases/multilines.rs"]);
+
+    let records = fs_operation::stream_files(config::Config::from(&args))
+        .flat_map(|b| {
+            output::to_jsonl_records(&b)
+                .map(|r| serde_json::to_value(r).unwrap())
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    assert_eq!(records.len(), 3);
+    assert_eq!(records[0]["schema_version"], output::JSON_SCHEMA_VERSION);
+    assert_eq!(records[0]["file_path"], "./tests/testcases/multilines.rs");
+    assert_eq!(records[0]["language"], "rs");
+    assert_eq!(records[0]["line_num"], 1);
+    assert_eq!(records[0]["end_line"], 3);
+    assert_eq!(records[0]["tail_lines"], serde_json::json!([2, 3]));
+    assert_eq!(records[2]["line_num"], 7);
+    assert_eq!(records[2]["tail_lines"], serde_json::json!([]));
+}
@@ -0,0 +1,145 @@
+mod common;
+
+use code_it_later_rs::*;
+use common::conf_on;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex};
+
+/// keywords regex is global, tests here cannot run at the same time
+static TEST_KEYWORD_DEF_LOCK: LazyLock<Mutex<()>> = LazyLock::new(|| Mutex::new(()));
+
+const CONTENT: &str = "fn main() {
+    //:= todo: a
+    //:= fix: b
+    //:= ToDo: c
+    //:= mark: d
+}
+";
+
+fn case(name: &str) -> PathBuf {
+    common::case("keyword-def", &format!("{}.rs", name), CONTENT)
+}
+
+/// the same keywords and aliases in all tests
+fn conf(path: &Path, args: &[&str]) -> config::Config {
+    let mut all = vec!["-k", "todo", "-k", "fixme", "-k", "mark"];
+    all.extend_from_slice(args);
+    conf_without_keywords(path, &all)
+}
+
+/// only the keyword definitions, without `-k`
+fn conf_without_keywords(path: &Path, args: &[&str]) -> config::Config {
+    config::clean_keywords_table();
+    let mut all = vec![
+        "--keyword-def",
+        "FIXME,fix=error:red:must fix",
+        "--keyword-def",
+        "TODO=warning",
+    ];
+    all.extend_from_slice(args);
+    conf_on(path, &all)
+}
+
+/// the line number and keyword of all crumbs
+fn scan(conf: config::Config) -> Vec<(usize, Option<String>)> {
+    fs_operation::handle_files(conf)
+        .flat_map(|b| output::to_jsonl_records(&b).collect::<Vec<_>>())
+        .map(|r| (r.crumb.line_num, r.crumb.keyword))
+        .collect()
+}
+
+#[test]
+fn test_keyword_normalized() {
+    let _lock = TEST_KEYWORD_DEF_LOCK.lock();
+    let path = case("normalized");
+    let kw = |k: &str| Some(k.to_string());
+
+    assert_eq!(
+        scan(conf(&path, &[])),
+        vec![
+            (2, kw("TODO")),
+            (3, kw("FIXME")),
+            (4, kw("TODO")),
+            (5, kw("mark"))
+        ]
+    );
+    assert_eq!(
+        scan(conf(&path, &["--sort", "severity"])),
+        vec![
+            (3, kw("FIXME")),
+            (2, kw("TODO")),
+            (4, kw("TODO")),
+            (5, kw("mark"))
+        ]
+    );
+    assert_eq!(
+        scan(conf(&path, &["--min-severity", "warning"]))
+            .into_iter()
+            .map(|(l, _)| l)
+            .collect::<Vec<_>>(),
+        vec![2, 3, 4]
+    );
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_fail_on() {
+    let _lock = TEST_KEYWORD_DEF_LOCK.lock();
+    let path = case("fail-on");
+
+    let err = prompt(conf(&path, &["-O", "list", "--fail-on", "error"])).unwrap_err();
+    assert_eq!(err, "1 crumbs at or above error severity");
+    let err = prompt(conf(&path, &["-O", "list", "--fail-on", "warn"])).unwrap_err();
+    assert_eq!(err, "3 crumbs at or above warning severity");
+
+    // the later definition replaces the one with same name
+    assert_eq!(
+        prompt(conf(
+            &path,
+            &[
+                "-O",
+                "list",
+                "--fail-on",
+                "error",
+                "--keyword-def",
+                "FIXME,fix=warning"
+            ]
+        )),
+        Ok(None)
+    );
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_keyword_def_without_keywords() {
+    let _lock = TEST_KEYWORD_DEF_LOCK.lock();
+    let path = case("without-keywords");
+    let kw = |k: &str| Some(k.to_string());
+
+    // the defined keywords are known, the other crumbs are still there
+    assert_eq!(
+        scan(conf_without_keywords(&path, &["--sort", "severity"])),
+        vec![
+            (3, kw("FIXME")),
+            (2, kw("TODO")),
+            (4, kw("TODO")),
+            (5, None)
+        ]
+    );
+    assert_eq!(
+        scan(conf_without_keywords(&path, &["--min-severity", "warning"]))
+            .into_iter()
+            .map(|(l, _)| l)
+            .collect::<Vec<_>>(),
+        vec![2, 3, 4]
+    );
+
+    let err = prompt(conf_without_keywords(
+        &path,
+        &["-O", "list", "--fail-on", "error"],
+    ))
+    .unwrap_err();
+    assert_eq!(err, "1 crumbs at or above error severity");
+    fs::remove_file(path).unwrap();
+}
@@ -0,0 +1,80 @@
+mod common;
+
+use code_it_later_rs::*;
+use std::fs;
+
+const CONTENT: &str = "fn main() {
+    //:= TODO: a
+    //:= todo: b
+    //:= C++: c
+    //:= JIRA-12: d
+    //:= JIRA-345: e
+    //:= MARK: f
+    //:= plain note
+    //:= see http://x
+}
+";
+
+/// the line number, keyword and view content of all crumbs
+fn scan(args: &[&str]) -> Vec<(usize, Option<String>, String)> {
+    let path = common::case("keyword-mode", "a.rs", CONTENT);
+    // the keywords regex is global, clean the one of last scan
+    config::clean_keywords_table();
+    let result = fs_operation::handle_files(common::conf_on(&path, args))
+        .flat_map(|b| output::to_jsonl_records(&b).collect::<Vec<_>>())
+        .map(|r| (r.crumb.line_num, r.crumb.keyword, r.crumb.view_content))
+        .collect();
+    fs::remove_file(path).unwrap();
+    result
+}
+
+fn lines(args: &[&str]) -> Vec<usize> {
+    scan(args).into_iter().map(|(l, _, _)| l).collect()
+}
+
+/// all cases in one test, they share the global keywords regex
+#[test]
+fn test_keyword_mode() {
+    let kw = |k: &str| Some(k.to_string());
+
+    // literal
+    assert_eq!(scan(&["-k", "C++"]), vec![(4, kw("C++"), "c".to_string())]);
+    assert_eq!(lines(&["-k", "TODO"]), vec![2, 3]);
+    assert_eq!(lines(&["-k", "TODO", "--case-sensitive"]), vec![2]);
+    assert_eq!(lines(&["-k", "JIRA-*"]), Vec::<usize>::new());
+
+    // glob and regex
+    assert_eq!(
+        scan(&["--keyword-mode", "glob", "-k", "JIRA-*"]),
+        vec![
+            (5, kw("JIRA-12"), "d".to_string()),
+            (6, kw("JIRA-345"), "e".to_string())
+        ]
+    );
+    assert_eq!(
+        scan(&["--keyword-mode", "regex", "-k", r"JIRA-(\d{2})"]),
+        vec![(5, kw("JIRA-12"), "d".to_string())]
+    );
+
+    // exclude and no keyword
+    assert_eq!(
+        lines(&[
+            "--keyword-mode",
+            "glob",
+            "--exclude-keyword",
+            "mark",
+            "--exclude-keyword",
+            "jira-*"
+        ]),
+        vec![2, 3, 4, 8, 9]
+    );
+    assert_eq!(
+        lines(&["-k", "TODO", "--exclude-keyword", "todo"]),
+        Vec::<usize>::new()
+    );
+    assert_eq!(lines(&["--no-keyword"]), vec![8, 9]);
+    assert_eq!(
+        lines(&["--no-keyword", "-k", "TODO", "-k", "MARK"]),
+        vec![4, 5, 6, 8, 9]
+    );
+}
@@ -0,0 +1,126 @@
+use clap::Parser;
+use code_it_later_rs::{args::*, *};
+use serde_json::{Value, json};
+use std::fs;
+
+fn frame(msgs: &[Value]) -> Vec<u8> {
+    let mut input = vec![];
+    for m in msgs {
+        let body = m.to_string();
+        input.extend_from_slice(
+            format!("Content-Length: {}\r\n\r\n{}", body.len(), body).as_bytes(),
+        );
+    }
+    input
+}
+
+fn unframe(mut output: &str) -> Vec<Value> {
+    let mut msgs = vec![];
+    while let Some((header, rest)) = output.split_once("\r\n\r\n") {
+        let len = header
+            .strip_prefix("Content-Length: ")
+            .unwrap()
+            .parse::<usize>()
+            .unwrap();
+        msgs.push(serde_json::from_str(&rest[..len]).unwrap());
+        output = &rest[len..];
+    }
+    msgs
+}
+
+#[test]
+fn test_lsp() {
+    let dir = std::env::temp_dir().join(format!("codeitlater-lsp-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("b.rs"), "fn b() {} //:= MARK: on disk\n").unwrap();
+    let root = format!("file://{}", dir.display());
+    let uri = format!("{}/a.rs", root);
+
+    let args = Args::parse_from(vec!["codeitlater", "-k", "TODO", "-k", "MARK", "lsp"]);
+    let conf = config::Config::from(&args);
+
+    let input = frame(&[
+        json!({"jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {"rootUri": root}}),
+        json!({"jsonrpc": "2.0", "method": "initialized", "params": {}}),
+        json!({"jsonrpc": "2.0", "method": "textDocument/didOpen", "params": {"textDocument": {
+            "uri": uri, "languageId": "rust", "version": 1,
+            "text": "fn a() {} //:= TODO: fix...\n          //:= it\n//:= MARK: unsaved\n",
+        }}}),
+        json!({"jsonrpc": "2.0", "id": 2, "method": "textDocument/codeAction", "params": {
+            "textDocument": {"uri": uri},
+            "range": {"start": {"line": 1, "character": 0}, "end": {"line": 1, "character": 0}},
+            "context": {"diagnostics": []},
+        }}),
+        json!({"jsonrpc": "2.0", "id": 3, "method": "workspace/symbol", "params": {"query": ""}}),
+        json!({"jsonrpc": "2.0", "id": 4, "method": "workspace/executeCommand", "params": {"command": "codeitlater.listCrumbs"}}),
+        json!({"jsonrpc": "2.0", "id": 5, "method": "unknown/method", "params": {}}),
+        json!({"jsonrpc": "2.0", "method": "textDocument/didClose", "params": {"textDocument": {"uri": uri}}}),
+        json!({"jsonrpc": "2.0", "id": 6, "method": "shutdown"}),
+        json!({"jsonrpc": "2.0", "method": "exit"}),
+    ]);
+    let mut output = vec![];
+    lsp::run(conf, input.as_slice(), &mut output).unwrap();
+    let msgs = unframe(std::str::from_utf8(&output).unwrap());
+    assert_eq!(msgs.len(), 8);
+
+    // initialize
+    assert_eq!(msgs[0]["id"], 1);
+    assert_eq!(
+        msgs[0]["result"]["capabilities"]["workspaceSymbolProvider"],
+        true
+    );
+
+    // diagnostics of opened document
+    assert_eq!(msgs[1]["method"], "textDocument/publishDiagnostics");
+    let diags = msgs[1]["params"]["diagnostics"].as_array().unwrap();
+    assert_eq!(diags.len(), 2);
+    assert_eq!(diags[0]["code"], "TODO");
+    assert_eq!(diags[0]["severity"], 2);
+    assert_eq!(diags[0]["message"], "fix...");
+    assert_eq!(
+        diags[0]["range"],
+        json!({"start": {"line": 0, "character": 10}, "end": {"line": 1, "character": 17}})
+    );
+    assert_eq!(diags[1]["code"], "MARK");
+    assert_eq!(diags[1]["severity"], 3);
+
+    // code actions of the crumb on the line 1
+    let actions = msgs[2]["result"].as_array().unwrap();
+    assert_eq!(actions.len(), 2);
+    assert_eq!(
+        actions[0]["edit"]["changes"][&uri],
+        json!([
+            {"range": {"start": {"line": 0, "character": 0}, "end": {"line": 0, "character": 27}}, "newText": "fn a() {} "},
+            {"range": {"start": {"line": 1, "character": 0}, "end": {"line": 1, "character": 17}}, "newText": "          "},
+        ])
+    );
+    assert_eq!(
+        actions[1]["edit"]["changes"][&uri][0]["newText"],
+        "fn a() {} // TODO: fix..."
+    );
+
+    // MARK crumbs of the disk file and the opened document
+    let mut symbols = msgs[3]["result"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|s| s["name"].as_str().unwrap().to_string())
+        .collect::<Vec<_>>();
+    symbols.sort();
+    assert_eq!(symbols, vec!["on disk", "unsaved"]);
+
+    // workspace listing
+    assert_eq!(
+        msgs[4]["result"]["schema_version"],
+        output::JSON_SCHEMA_VERSION
+    );
+    assert_eq!(msgs[4]["result"]["breads"].as_array().unwrap().len(), 2);
+
+    assert_eq!(msgs[5]["error"]["code"], -32601);
+
+    // closed document clear its diagnostics
+    assert_eq!(msgs[6]["params"]["diagnostics"], json!([]));
+    assert_eq!(msgs[7], json!({"jsonrpc": "2.0", "id": 6, "result": null}));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,98 @@
+mod common;
+
+use code_it_later_rs::*;
+use common::{case, conf_on};
+use std::fs;
+
+const HTML: &str = "<p>a</p> <!--:= TODO: fix the link --> <b/>
+<!--:= MARK: next...-->
+<!--:= line -->
+<!-- normal comment -->
+";
+
+const TXT: &str = "notes
+  := TODO: plain text...
+  := crumb
+not := crumb
+";
+
+/// the line number and the content of all crumbs
+fn scan(conf: config::Config) -> Vec<(usize, String)> {
+    fs_operation::handle_files(conf)
+        .flat_map(|b| output::to_jsonl_records(&b).collect::<Vec<_>>())
+        .map(|r| (r.crumb.line_num, r.crumb.view_content))
+        .collect()
+}
+
+/// the txt regex is global, all cases are in one test
+#[test]
+fn test_markup_and_txt() {
+    let html = case("markup", "a.html", HTML);
+    let md = case("markup", "b.md", "# title\n<!--:= FIXME: md -->\n");
+    let txt = case("markup", "c.txt", TXT);
+
+    assert_eq!(
+        scan(conf_on(&html, &[])),
+        vec![
+            (1, "TODO: fix the link".to_string()),
+            (2, "MARK: next line".to_string())
+        ]
+    );
+    assert_eq!(scan(conf_on(&md, &[])), vec![(2, "FIXME: md".to_string())]);
+
+    // the txt files are only scanned in txt mode
+    assert_eq!(scan(conf_on(&txt, &[])), vec![]);
+    assert_eq!(
+        scan(conf_on(&txt, &["--txt"])),
+        vec![(2, "TODO: plain text crumb".to_string())]
+    );
+
+    let bread = fs_operation::handle_files(conf_on(&html, &[]))
+        .next()
+        .unwrap();
+    fs_operation::restore_the_crumb(bread).unwrap();
+    assert_eq!(
+        fs::read_to_string(&html).unwrap(),
+        "<p>a</p> <!-- TODO: fix the link --> <b/>
+<!-- MARK: next...-->
+<!-- line -->
+<!-- normal comment -->
+"
+    );
+
+    fs::write(&html, HTML).unwrap();
+    let bread = fs_operation::handle_files(conf_on(&html, &[]))
+        .next()
+        .unwrap();
+    fs_operation::delete_the_crumbs(bread).unwrap();
+    assert_eq!(
+        fs::read_to_string(&html).unwrap(),
+        "<p>a</p>  <b/>\n<!-- normal comment -->\n"
+    );
+
+    let bread = fs_operation::handle_files(conf_on(&txt, &["--txt"]))
+        .next()
+        .unwrap();
+    fs_operation::restore_the_crumb(bread).unwrap();
+    assert_eq!(
+        fs::read_to_string(&txt).unwrap(),
+        "notes\n  TODO: plain text...\n  crumb\nnot := crumb\n"
+    );
+
+    fs::write(&txt, TXT).unwrap();
+    let bread = fs_operation::handle_files(conf_on(&txt, &["--txt"]))
+        .next()
+        .unwrap();
+    fs_operation::delete_the_crumbs(bread).unwrap();
+    assert_eq!(fs::read_to_string(&txt).unwrap(), "notes\nnot := crumb\n");
+
+    fs_operation::add_crumb(&md, 1, "new", false).unwrap();
+    assert_eq!(
+        fs::read_to_string(&md).unwrap(),
+        "<!--:= new -->\n# title\n<!--:= FIXME: md -->\n"
+    );
+
+    [html, md, txt]
+        .iter()
+        .for_each(|p| fs::remove_file(p).unwrap());
+}
@@ -0,0 +1,146 @@
+use clap::Parser;
+use code_it_later_rs::{args::*, *};
+use serde_json::{Value, json};
+use std::fs;
+
+fn call(id: u64, name: &str, arguments: Value) -> Value {
+    json!({"jsonrpc": "2.0", "id": id, "method": "tools/call", "params": {"name": name, "arguments": arguments}})
+}
+
+fn run(conf: config::Config, msgs: &[Value]) -> Vec<Value> {
+    let input = msgs
+        .iter()
+        .map(|m| m.to_string() + "\n")
+        .collect::<String>();
+    let mut output = vec![];
+    mcp::run(conf, input.as_bytes(), &mut output).unwrap();
+    std::str::from_utf8(&output)
+        .unwrap()
+        .lines()
+        .map(|l| serde_json::from_str(l).unwrap())
+        .collect()
+}
+
+#[test]
+fn test_mcp() {
+    let dir = std::env::temp_dir().join(format!("codeitlater-mcp-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("a.rs");
+    fs::write(
+        &file,
+        "fn a() {\n    //:= TODO: handle error\n    1\n}\n//:= FIXME: rename\n",
+    )
+    .unwrap();
+    let dir_s = dir.to_str().unwrap();
+    let conf = || config::Config::from(&Args::parse_from(vec!["codeitlater", dir_s, "mcp"]));
+    let id = |line: usize| format!("{}:{}", file.display(), line);
+
+    let msgs = run(
+        conf(),
+        &[
+            json!({"jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {"protocolVersion": "2025-03-26", "capabilities": {}}}),
+            json!({"jsonrpc": "2.0", "method": "notifications/initialized"}),
+            json!({"jsonrpc": "2.0", "id": 2, "method": "tools/list"}),
+            call(3, "list_crumbs", json!({"keyword": "todo"})),
+            call(4, "get_crumb_context", json!({"id": id(2), "lines": 1})),
+            call(5, "get_crumb_context", json!({"id": "/etc/passwd.rs:1"})),
+            call(6, "unknown_tool", json!({})),
+            call(
+                7,
+                "get_crumb_context",
+                json!({"id": id(2), "lines": u64::MAX}),
+            ),
+        ],
+    );
+    assert_eq!(msgs.len(), 7);
+    assert_eq!(msgs[0]["result"]["protocolVersion"], "2025-03-26");
+    let tools = msgs[1]["result"]["tools"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|t| t["name"].as_str().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        tools,
+        vec![
+            "list_crumbs",
+            "get_crumb_context",
+            "resolve_crumb",
+            "restore_crumb"
+        ]
+    );
+
+    let crumbs = &msgs[2]["result"]["structuredContent"]["crumbs"];
+    assert_eq!(crumbs.as_array().unwrap().len(), 1);
+    assert_eq!(crumbs[0]["id"], id(2));
+    assert_eq!(crumbs[0]["content"], "TODO: handle error");
+
+    let context = &msgs[3]["result"]["structuredContent"];
+    assert_eq!(context["crumb"]["id"], id(2));
+    assert_eq!(
+        context["context"],
+        json!([
+            {"line_num": 1, "text": "fn a() {"},
+            {"line_num": 2, "text": "    //:= TODO: handle error"},
+            {"line_num": 3, "text": "    1"},
+        ])
+    );
+
+    assert_eq!(msgs[4]["result"]["isError"], true);
+    assert_eq!(msgs[5]["error"]["code"], -32602);
+    // the huge context is the whole file
+    assert_eq!(
+        msgs[6]["result"]["structuredContent"]["context"]
+            .as_array()
+            .unwrap()
+            .len(),
+        5
+    );
+
+    // resolve and restore change the file
+    let msgs = run(
+        conf(),
+        &[
+            call(1, "resolve_crumb", json!({"id": id(2)})),
+            call(2, "restore_crumb", json!({"id": id(5)})),
+            call(3, "resolve_crumb", json!({"id": id(2)})),
+        ],
+    );
+    assert_eq!(msgs[0]["result"]["isError"], false);
+    assert_eq!(msgs[1]["result"]["isError"], false);
+    assert_eq!(msgs[2]["result"]["isError"], true);
+    assert_eq!(
+        fs::read_to_string(&file).unwrap(),
+        "fn a() {\n    \n    1\n}\n// FIXME: rename\n"
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_mcp_notebook_context() {
+    let path = "tests/testcases/notebook.ipynb";
+    let conf = config::Config::from(&Args::parse_from(vec!["codeitlater", path, "mcp"]));
+
+    let msgs = run(
+        conf,
+        &[call(
+            1,
+            "get_crumb_context",
+            json!({"id": format!("{}:2:2", path), "lines": 1}),
+        )],
+    );
+
+    // the lines are in the cell, not in the json document
+    let context = &msgs[0]["result"]["structuredContent"];
+    assert_eq!(context["crumb"]["cell"], 2);
+    assert_eq!(
+        context["context"],
+        json!([
+            {"line_num": 1, "text": "import os  #:= TODO: fix it"},
+            {"line_num": 2, "text": "#:= FIXME: a..."},
+            {"line_num": 3, "text": "#:= b"},
+            {"line_num": 4, "text": "x = 1"},
+        ])
+    );
+}
@@ -0,0 +1,125 @@
+mod common;
+
+use code_it_later_rs::*;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const NOTEBOOK: &str = r##"{
+ "cells": [
+  {
+   "cell_type": "markdown",
+   "metadata": {},
+   "source": [
+    "# Title\n",
+    "<!--:= MARK: doc -->"
+   ]
+  },
+  {
+   "cell_type": "code",
+   "execution_count": 1,
+   "metadata": {},
+   "outputs": [],
+   "source": [
+    "import os  #:= TODO: fix it\n",
+    "#:= FIXME: a...\n",
+    "#:= b\n",
+    "x = 1"
+   ]
+  },
+  {
+   "cell_type": "raw",
+   "metadata": {},
+   "source": "#:= not scanned"
+  }
+ ],
+ "metadata": {
+  "kernelspec": {
+   "display_name": "Python 3",
+   "language": "python",
+   "name": "python3"
+  }
+ },
+ "nbformat": 4,
+ "nbformat_minor": 5
+}
+"##;
+
+fn case(name: &str, content: &str) -> PathBuf {
+    common::case("notebook", &format!("{}.ipynb", name), content)
+}
+
+fn conf(path: &Path) -> config::Config {
+    common::conf_on(path, &[])
+}
+
+/// the cell, line number and content of all crumbs
+fn scan(path: &Path) -> Vec<(Option<usize>, usize, String)> {
+    fs_operation::handle_files(conf(path))
+        .flat_map(|b| output::to_jsonl_records(&b).collect::<Vec<_>>())
+        .map(|r| (r.crumb.cell, r.crumb.line_num, r.crumb.view_content))
+        .collect()
+}
+
+#[test]
+fn test_notebook_crumbs() {
+    let path = case("scan", NOTEBOOK);
+    assert_eq!(
+        scan(&path),
+        vec![
+            (Some(1), 2, "MARK: doc".to_string()),
+            (Some(2), 1, "TODO: fix it".to_string()),
+            (Some(2), 2, "FIXME: a b".to_string()),
+        ]
+    );
+
+    let bread = fs_operation::handle_files(conf(&path)).next().unwrap();
+    assert!(
+        bread
+            .to_string()
+            .contains("|-- Cell 2 Line 2: FIXME: a b\n")
+    );
+
+    // the kernel language decides the comment symbol
+    let rust = case(
+        "rust",
+        &NOTEBOOK
+            .replace("#:=", "//:=")
+            .replace("\"python\"", "\"rust\""),
+    );
+    assert_eq!(scan(&rust).len(), 3);
+
+    fs::remove_file(path).unwrap();
+    fs::remove_file(rust).unwrap();
+}
+
+#[test]
+fn test_notebook_delete_and_restore() {
+    let path = case("clean", NOTEBOOK);
+
+    let bread = fs_operation::handle_files(conf(&path)).next().unwrap();
+    fs_operation::restore_the_crumb(bread).unwrap();
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        NOTEBOOK
+            .replace("<!--:= MARK", "<!-- MARK")
+            .replace("#:= TODO", "# TODO")
+            .replace("#:= FIXME", "# FIXME")
+            .replace("#:= b", "# b")
+    );
+
+    fs::write(&path, NOTEBOOK).unwrap();
+    let bread = fs_operation::handle_files(conf(&path)).next().unwrap();
+    fs_operation::delete_the_crumbs(bread).unwrap();
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        NOTEBOOK
+            .replace(
+                "    \"# Title\\n\",\n    \"<!--:= MARK: doc -->\"",
+                "    \"# Title\""
+            )
+            .replace("  #:= TODO: fix it", "  ")
+            .replace("    \"#:= FIXME: a...\\n\",\n    \"#:= b\\n\",\n", "")
+    );
+
+    fs::remove_file(path).unwrap();
+}
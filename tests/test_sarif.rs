@@ -0,0 +1,163 @@
+mod common;
+
+use clap::Parser;
+use code_it_later_rs::{args::*, *};
+use std::fs;
+use std::sync::{LazyLock, Mutex};
+
+/// keywords regex is global, tests here cannot run at the same time
+static TEST_SARIF_LOCK: LazyLock<Mutex<()>> = LazyLock::new(|| Mutex::new(()));
+
+fn sarif_validator() -> jsonschema::Validator {
+    let schema: serde_json::Value = serde_json::from_str(
+        &fs::read_to_string("./tests/testcases/sarif-schema-2.1.0.json").unwrap(),
+    )
+    .unwrap();
+    jsonschema::validator_for(&schema).unwrap()
+}
+
+#[test]
+fn test_sarif_output() {
+    let _lock = TEST_SARIF_LOCK.lock();
+    config::clean_keywords_table();
+    let args = Args::parse_from(vec![
+        "codeitlater",
+        "--show-ignored",
+        "./tests/testcases/test.rs",
+        "./tests/testcases/multilines.rs",
+    ]);
+    let conf = config::Config::from(&args);
+    let breads = fs_operation::handle_files(conf).collect::<Vec<_>>();
+
+    let sarif = serde_json::to_value(output::to_sarif(
+        &breads,
+        &Default::default(),
+        &Default::default(),
+    ))
+    .unwrap();
+    let validator = sarif_validator();
+    let errors = validator
+        .iter_errors(&sarif)
+        .map(|e| format!("{} at {}", e, e.instance_path))
+        .collect::<Vec<_>>();
+    assert!(errors.is_empty(), "sarif is invalid: {:?}", errors);
+
+    assert_eq!(sarif["version"], "2.1.0");
+    let results = sarif["runs"][0]["results"].as_array().unwrap();
+    assert_eq!(results.len(), 6);
+    assert_eq!(sarif["runs"][0]["tool"]["driver"]["rules"][0]["id"], "crumb");
+
+    let first = &results[0];
+    assert_eq!(first["ruleId"], "crumb");
+    assert_eq!(first["message"]["text"], "this is rust");
+    assert_eq!(
+        first["locations"][0]["physicalLocation"]["artifactLocation"]["uri"],
+        "tests/testcases/test.rs"
+    );
+    assert_eq!(
+        first["locations"][0]["physicalLocation"]["region"]["startLine"],
+        1
+    );
+    assert_eq!(
+        first["locations"][0]["physicalLocation"]["region"]["startColumn"],
+        1
+    );
+
+    // ignored crumb is suppressed
+    assert_eq!(results[2]["suppressions"][0]["kind"], "inSource");
+
+    // multi-lines crumb has the end line
+    assert_eq!(
+        results[3]["locations"][0]["physicalLocation"]["region"]["endLine"],
+        3
+    );
+}
+
+#[test]
+fn test_sarif_keyword_rules() {
+    let _lock = TEST_SARIF_LOCK.lock();
+    let args = Args::parse_from(vec![
+        "codeitlater",
+        "-k",
+        "TODO",
+        "-k",
+        "MARK",
+        "./tests/testcases/keywords.lisp",
+    ]);
+    let conf = config::Config::from(&args);
+    let breads = fs_operation::handle_files(conf).collect::<Vec<_>>();
+
+    let sarif = serde_json::to_value(output::to_sarif(
+        &breads,
+        &Default::default(),
+        &Default::default(),
+    ))
+    .unwrap();
+    assert!(sarif_validator().is_valid(&sarif));
+
+    let rules = sarif["runs"][0]["tool"]["driver"]["rules"]
+        .as_array()
+        .unwrap();
+    assert_eq!(rules.len(), 2);
+    assert_eq!(rules[0]["id"], "TODO");
+    assert_eq!(rules[1]["id"], "MARK");
+
+    let results = sarif["runs"][0]["results"].as_array().unwrap();
+    assert_eq!(results[0]["ruleId"], "TODO");
+    assert_eq!(results[0]["level"], "warning");
+    assert_eq!(results[1]["level"], "note");
+    assert_eq!(results[0]["ruleIndex"], 0);
+    assert_eq!(results[1]["ruleId"], "MARK");
+    assert_eq!(results[1]["ruleIndex"], 1);
+    assert_eq!(results[2]["ruleIndex"], 1);
+}
+
+#[test]
+fn test_sarif_notebook() {
+    let _lock = TEST_SARIF_LOCK.lock();
+    config::clean_keywords_table();
+    let args = Args::parse_from(vec!["codeitlater", "./tests/testcases/notebook.ipynb"]);
+    let conf = config::Config::from(&args);
+    let breads = fs_operation::handle_files(conf).collect::<Vec<_>>();
+
+    let sarif = serde_json::to_value(output::to_sarif(
+        &breads,
+        &Default::default(),
+        &Default::default(),
+    ))
+    .unwrap();
+    assert!(sarif_validator().is_valid(&sarif));
+
+    // the line of cell isn't the line of notebook file
+    let location = &sarif["runs"][0]["results"][1]["locations"][0];
+    assert!(location["physicalLocation"].get("region").is_none());
+    assert_eq!(
+        location["physicalLocation"]["artifactLocation"]["uri"],
+        "tests/testcases/notebook.ipynb"
+    );
+    assert_eq!(location["logicalLocations"][0]["name"], "cell 2");
+    assert_eq!(location["logicalLocations"][0]["kind"], "cell");
+    assert_eq!(
+        location["logicalLocations"][0]["fullyQualifiedName"],
+        "tests/testcases/notebook.ipynb:cell2:1"
+    );
+}
+
+#[test]
+fn test_sarif_utf16_column() {
+    let _lock = TEST_SARIF_LOCK.lock();
+    config::clean_keywords_table();
+    // 4 bytes and 2 utf-16 code units of 😀, 2 bytes and 1 unit of é
+    let path = common::case("sarif", "utf16.rs", "let s = \"😀é\"; //:= TODO: x\n");
+    let breads = fs_operation::handle_files(common::conf_on(&path, &[])).collect::<Vec<_>>();
+
+    let sarif = serde_json::to_value(output::to_sarif(
+        &breads,
+        &Default::default(),
+        &Default::default(),
+    ))
+    .unwrap();
+    let location = &sarif["runs"][0]["results"][0]["locations"][0];
+    assert_eq!(location["physicalLocation"]["region"]["startColumn"], 16);
+    fs::remove_file(path).unwrap();
+}
@@ -0,0 +1,70 @@
+mod common;
+
+use code_it_later_rs::*;
+use common::conf_on;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// copy the clean case to a temp file for every test
+fn case(name: &str) -> PathBuf {
+    common::case(
+        "select",
+        &format!("{}.rs", name),
+        &fs::read_to_string("./tests/testcases/clean_case_0.rs.bkp").unwrap(),
+    )
+}
+
+fn run(path: &Path, args: &[&str]) -> Option<std::collections::HashSet<String>> {
+    prompt(conf_on(path, args)).unwrap()
+}
+
+#[test]
+fn test_no_input_changes_nothing() {
+    let path = case("no-input");
+    assert_eq!(run(&path, &["-D", "--no-input"]), None);
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        fs::read_to_string("./tests/testcases/clean_case_0.rs.bkp").unwrap()
+    );
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_delete_selected() {
+    let path = case("delete");
+    let changed = run(&path, &["-D", "--yes", "--select-lines", "4-7"]).unwrap();
+    assert!(changed.contains(path.to_str().unwrap()));
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        "//:= line 0\naaaa\nbbb\nccc\nddd \n//:= !TODO: ignore keyword\n"
+    );
+
+    let id = format!("{}:1", path.display());
+    run(&path, &["-D", "-y", "--select-id", &id]);
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        "aaaa\nbbb\nccc\nddd \n//:= !TODO: ignore keyword\n"
+    );
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_restore_selected() {
+    let path = case("restore");
+    run(
+        &path,
+        &["-R", "--yes", "--select-ignored", "true", "-O", "json"],
+    );
+    assert!(
+        fs::read_to_string(&path)
+            .unwrap()
+            .ends_with("ddd //:= del\n// !TODO: ignore keyword\n")
+    );
+
+    // nothing selected
+    assert_eq!(
+        run(&path, &["-R", "-y", "--select-regex", "^nothing"]),
+        None
+    );
+    fs::remove_file(&path).unwrap();
+}